@@ -0,0 +1,521 @@
+// WAV / raw PCM からの無音検出（ffmpeg silencedetect と同じ判定）
+//
+// 全チャンネルの振幅が閾値未満のサンプルが最小時間以上続く区間を無音区間とする。
+// silencedetect=mono=0 と同様に、開始は最初の無音サンプル、終了は最初の非無音サンプルの時刻。
+// 閾値を推定する場合（--silence-noise auto）は 10ms ごとのピークレベルで判定する。
+
+use std::io::{Read, Seek, SeekFrom};
+
+use crate::SilenceSegment;
+use crate::threshold::{self, ThresholdReport};
+
+const READ_BUFFER_BYTES: usize = 64 * 1024;
+const LEVEL_WINDOW_MS: u64 = 10; // 閾値の推定でピークレベルを集計する単位
+const WAV_FMT_READ_BYTES: usize = 40; // fmt チャンクのうち読む長さ（WAVE_FORMAT_EXTENSIBLE まで）
+const WAV_FMT_MAX_SIZE: u32 = 1024; // これを超える fmt チャンクは不正とみなす
+
+/// サンプルの形式（リトルエンディアン）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SampleFormat {
+    S16,
+    S24,
+    S32,
+    F32,
+}
+
+impl SampleFormat {
+    /// ffmpeg の -f の名前（s16le / s24le / s32le / f32le）
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "s16le" => Some(SampleFormat::S16),
+            "s24le" => Some(SampleFormat::S24),
+            "s32le" => Some(SampleFormat::S32),
+            "f32le" => Some(SampleFormat::F32),
+            _ => None,
+        }
+    }
+
+    fn bytes(self) -> usize {
+        match self {
+            SampleFormat::S16 => 2,
+            SampleFormat::S24 => 3,
+            SampleFormat::S32 | SampleFormat::F32 => 4,
+        }
+    }
+
+    /// -1.0〜1.0 に正規化した振幅の絶対値
+    fn amplitude(self, bytes: &[u8]) -> f64 {
+        match self {
+            SampleFormat::S16 => i16::from_le_bytes([bytes[0], bytes[1]]).unsigned_abs() as f64 / 32768.0,
+            SampleFormat::S24 => {
+                let value = i32::from_le_bytes([0, bytes[0], bytes[1], bytes[2]]) >> 8;
+                value.unsigned_abs() as f64 / 8_388_608.0
+            }
+            SampleFormat::S32 => {
+                i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]).unsigned_abs() as f64 / 2_147_483_648.0
+            }
+            SampleFormat::F32 => f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]).abs() as f64,
+        }
+    }
+}
+
+/// PCMの形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PcmFormat {
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub sample_format: SampleFormat,
+}
+
+impl Default for PcmFormat {
+    fn default() -> Self {
+        PcmFormat {
+            sample_rate: 48000,
+            channels: 2,
+            sample_format: SampleFormat::S16,
+        }
+    }
+}
+
+/// 無音判定のパラメータ（silencedetect の n / d）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SilenceParams {
+    pub noise_db: f64,
+    pub min_duration_sec: f64,
+    /// noise_db の代わりに音声のレベル分布から閾値を推定する
+    pub adaptive: bool,
+}
+
+impl Default for SilenceParams {
+    fn default() -> Self {
+        SilenceParams {
+            noise_db: -40.0,
+            min_duration_sec: 0.3,
+            adaptive: false,
+        }
+    }
+}
+
+/// 音声入力の設定
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AudioOptions {
+    /// raw PCM の形式（WAVではヘッダから取得）
+    pub pcm: PcmFormat,
+    pub silence: SilenceParams,
+}
+
+/// 無音検出の結果
+#[derive(Debug)]
+pub struct AudioAnalysis {
+    pub silence_segments: Vec<SilenceSegment>,
+    pub duration_ms: i64,
+    /// 推定した閾値（adaptive の場合）
+    pub threshold: Option<ThresholdReport>,
+}
+
+fn read_u16(bytes: &[u8]) -> u16 {
+    u16::from_le_bytes([bytes[0], bytes[1]])
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+/// WAVのヘッダを読み、data チャンクの直前まで進める
+/// 戻り値は (形式, data チャンクの長さ)。パイプ出力で長さが不明（0 / 0xFFFFFFFF）の場合は None
+pub fn read_wav_header(reader: &mut impl Read) -> Result<(PcmFormat, Option<u64>), String> {
+    let mut header = [0u8; 12];
+    reader
+        .read_exact(&mut header)
+        .map_err(|e| format!("Failed to read WAV header: {}", e))?;
+    if &header[0..4] != b"RIFF" || &header[8..12] != b"WAVE" {
+        return Err("Not a RIFF/WAVE file".to_string());
+    }
+
+    let mut format = None;
+    loop {
+        let mut chunk = [0u8; 8];
+        reader
+            .read_exact(&mut chunk)
+            .map_err(|_| "WAV file has no data chunk".to_string())?;
+        let size = read_u32(&chunk[4..8]);
+        match &chunk[0..4] {
+            b"fmt " => {
+                // チャンクの長さは信頼できないため、必要な先頭部分だけを読み残りは読み飛ばす
+                if !(16..=WAV_FMT_MAX_SIZE).contains(&size) {
+                    return Err(format!("Invalid WAV fmt chunk size: {}", size));
+                }
+                let mut buffer = [0u8; WAV_FMT_READ_BYTES];
+                let len = (size as usize).min(WAV_FMT_READ_BYTES);
+                reader
+                    .read_exact(&mut buffer[..len])
+                    .map_err(|e| format!("Failed to read WAV fmt chunk: {}", e))?;
+                io_skip(reader, (size as usize - len) as u64 + (size as u64 & 1))?;
+                let body = &buffer[..len];
+                // WAVE_FORMAT_EXTENSIBLE はサブフォーマットGUIDの先頭2バイトが形式
+                let mut tag = read_u16(&body[0..2]);
+                if tag == 0xFFFE && body.len() >= 26 {
+                    tag = read_u16(&body[24..26]);
+                }
+                let bits = read_u16(&body[14..16]);
+                let sample_format = match (tag, bits) {
+                    (1, 16) => SampleFormat::S16,
+                    (1, 24) => SampleFormat::S24,
+                    (1, 32) => SampleFormat::S32,
+                    (3, 32) => SampleFormat::F32,
+                    _ => return Err(format!("Unsupported WAV format: tag {} with {} bits", tag, bits)),
+                };
+                format = Some(PcmFormat {
+                    sample_rate: read_u32(&body[4..8]),
+                    channels: read_u16(&body[2..4]),
+                    sample_format,
+                });
+            }
+            b"data" => {
+                let format = format.ok_or("WAV data chunk before fmt chunk")?;
+                let length = (size != 0 && size != u32::MAX).then_some(size as u64);
+                return Ok((format, length));
+            }
+            _ => {
+                // その他のチャンク（LIST 等）は読み飛ばす
+                let skip = size as u64 + (size as u64 & 1);
+                io_skip(reader, skip)?;
+            }
+        }
+    }
+}
+
+fn io_skip(reader: &mut impl Read, bytes: u64) -> Result<(), String> {
+    let copied = std::io::copy(&mut reader.take(bytes), &mut std::io::sink())
+        .map_err(|e| format!("Failed to read WAV chunk: {}", e))?;
+    if copied < bytes {
+        return Err("Unexpected end of WAV file".to_string());
+    }
+    Ok(())
+}
+
+/// 無音のフレームが続く区間を集める（単位はフレームまたはレベルの集計区間）
+struct SilenceRuns {
+    min_length: u64,
+    position: u64,
+    start: Option<u64>,
+    runs: Vec<(u64, u64)>,
+}
+
+impl SilenceRuns {
+    fn new(min_length: u64) -> Self {
+        SilenceRuns {
+            min_length: min_length.max(1),
+            position: 0,
+            start: None,
+            runs: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, silent: bool) {
+        match (silent, self.start) {
+            (true, None) => self.start = Some(self.position),
+            (false, Some(start)) => {
+                self.close(start);
+                self.start = None;
+            }
+            _ => {}
+        }
+        self.position += 1;
+    }
+
+    fn close(&mut self, start: u64) {
+        if self.position - start >= self.min_length {
+            self.runs.push((start, self.position));
+        }
+    }
+
+    /// 終端まで無音が続いた場合も区間とする
+    fn finish(mut self) -> Vec<(u64, u64)> {
+        if let Some(start) = self.start {
+            self.close(start);
+        }
+        self.runs
+    }
+}
+
+/// PCMをフレームごとに読み、全チャンネルの振幅の最大値を渡す。戻り値はフレーム数
+fn for_each_frame(reader: &mut impl Read, format: PcmFormat, mut f: impl FnMut(f64)) -> Result<u64, String> {
+    if format.sample_rate == 0 || format.channels == 0 {
+        return Err("Invalid PCM format: sample rate and channels must be positive".to_string());
+    }
+    let sample_bytes = format.sample_format.bytes();
+    let frame_bytes = sample_bytes * format.channels as usize;
+
+    let mut buffer = vec![0u8; READ_BUFFER_BYTES];
+    let mut filled = 0;
+    let mut frames: u64 = 0;
+    loop {
+        let read = reader
+            .read(&mut buffer[filled..])
+            .map_err(|e| format!("Failed to read PCM data: {}", e))?;
+        if read == 0 {
+            break;
+        }
+        filled += read;
+
+        let complete = filled - filled % frame_bytes;
+        for bytes in buffer[..complete].chunks_exact(frame_bytes) {
+            let peak = bytes
+                .chunks_exact(sample_bytes)
+                .map(|sample| format.sample_format.amplitude(sample))
+                .fold(0.0, f64::max);
+            f(peak);
+            frames += 1;
+        }
+        // 途中までのフレームを先頭に移動
+        buffer.copy_within(complete..filled, 0);
+        filled -= complete;
+    }
+    Ok(frames)
+}
+
+/// PCMのサンプルから無音区間を検出（silencedetect=mono=0 と同じ判定）
+/// params.adaptive の場合は閾値を推定してから検出する
+pub fn detect_silence(
+    reader: &mut impl Read,
+    format: PcmFormat,
+    params: SilenceParams,
+) -> Result<AudioAnalysis, String> {
+    if params.adaptive {
+        return detect_silence_adaptive(reader, format, params);
+    }
+    let threshold = 10f64.powf(params.noise_db / 20.0);
+    let min_frames = (params.min_duration_sec * format.sample_rate as f64).round() as u64;
+    let mut runs = SilenceRuns::new(min_frames);
+    let frames = for_each_frame(reader, format, |peak| runs.push(peak < threshold))?;
+
+    let to_ms = |frame: u64| (frame * 1000 / format.sample_rate as u64) as i64;
+    Ok(AudioAnalysis {
+        silence_segments: runs
+            .finish()
+            .into_iter()
+            .map(|(start, end)| silence_segment(to_ms(start), to_ms(end)))
+            .collect(),
+        duration_ms: to_ms(frames),
+        threshold: None,
+    })
+}
+
+/// レベルのヒストグラムから録画ごとの閾値を推定して無音区間を検出
+/// 音声を2回読めないため、LEVEL_WINDOW_MS ごとのピークを保持して判定する（時刻の精度は LEVEL_WINDOW_MS）
+fn detect_silence_adaptive(
+    reader: &mut impl Read,
+    format: PcmFormat,
+    params: SilenceParams,
+) -> Result<AudioAnalysis, String> {
+    let window_frames = (format.sample_rate as u64 * LEVEL_WINDOW_MS / 1000).max(1);
+    let mut peaks: Vec<f32> = Vec::new();
+    let mut window_peak = 0.0f64;
+    let mut window_len = 0;
+    let frames = for_each_frame(reader, format, |peak| {
+        window_peak = window_peak.max(peak);
+        window_len += 1;
+        if window_len == window_frames {
+            peaks.push(window_peak as f32);
+            window_peak = 0.0;
+            window_len = 0;
+        }
+    })?;
+    if window_len > 0 {
+        peaks.push(window_peak as f32);
+    }
+
+    let levels: Vec<f64> = peaks.iter().map(|&p| threshold::amplitude_to_db(p as f64)).collect();
+    let report = threshold::estimate_noise_threshold(&levels);
+    let amplitude = 10f64.powf(report.noise_db / 20.0);
+    let min_frames = (params.min_duration_sec * format.sample_rate as f64).round() as u64;
+    let mut runs = SilenceRuns::new(min_frames.div_ceil(window_frames));
+    for &peak in &peaks {
+        runs.push((peak as f64) < amplitude);
+    }
+
+    let to_ms = |window: u64| ((window * window_frames).min(frames) * 1000 / format.sample_rate as u64) as i64;
+    Ok(AudioAnalysis {
+        silence_segments: runs
+            .finish()
+            .into_iter()
+            .map(|(start, end)| silence_segment(to_ms(start), to_ms(end)))
+            .collect(),
+        duration_ms: (frames * 1000 / format.sample_rate as u64) as i64,
+        threshold: Some(report),
+    })
+}
+
+/// ファイルの start_ms〜end_ms の範囲だけを無音検出（data_start は PCM データの先頭のバイト位置）
+/// 戻り値の時刻はファイル先頭からの時刻
+pub fn detect_silence_range(
+    file: &mut (impl Read + Seek),
+    data_start: u64,
+    format: PcmFormat,
+    start_ms: i64,
+    end_ms: i64,
+    params: SilenceParams,
+) -> Result<Vec<SilenceSegment>, String> {
+    let frame_bytes = (format.sample_format.bytes() * format.channels as usize) as u64;
+    let to_frame = |ms: i64| ms.max(0) as u64 * format.sample_rate as u64 / 1000;
+    let first_frame = to_frame(start_ms);
+    let frames = to_frame(end_ms).saturating_sub(first_frame);
+    file.seek(SeekFrom::Start(data_start + first_frame * frame_bytes))
+        .map_err(|e| format!("Failed to seek PCM data: {}", e))?;
+
+    let offset_ms = (first_frame * 1000 / format.sample_rate.max(1) as u64) as i64;
+    let analysis = detect_silence(&mut file.take(frames * frame_bytes), format, params)?;
+    Ok(analysis
+        .silence_segments
+        .into_iter()
+        .map(|s| silence_segment(s.start_ms + offset_ms, s.end_ms + offset_ms))
+        .collect())
+}
+
+fn silence_segment(start_ms: i64, end_ms: i64) -> SilenceSegment {
+    SilenceSegment {
+        start_ms,
+        end_ms,
+        duration_ms: end_ms - start_ms,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 8kHz モノラル s16 の信号（区間ごとの振幅）
+    fn pcm(parts: &[(f64, i16)]) -> Vec<u8> {
+        parts
+            .iter()
+            .flat_map(|&(seconds, amplitude)| {
+                (0..(seconds * 8000.0) as usize).flat_map(move |i| {
+                    // 符号を交互にした矩形波
+                    let value = if i % 2 == 0 { amplitude } else { -amplitude };
+                    value.to_le_bytes()
+                })
+            })
+            .collect()
+    }
+
+    const MONO_8K: PcmFormat = PcmFormat {
+        sample_rate: 8000,
+        channels: 1,
+        sample_format: SampleFormat::S16,
+    };
+
+    #[test]
+    fn test_detect_silence() {
+        // -40dB = 振幅 327.68。1s 音 → 0.5s 無音 → 1s 音 → 0.2s 無音（短い） → 1s 音 → 0.4s 無音（終端）
+        let data = pcm(&[(1.0, 10000), (0.5, 100), (1.0, 10000), (0.2, 0), (1.0, 10000), (0.4, 300)]);
+        let analysis = detect_silence(&mut data.as_slice(), MONO_8K, SilenceParams::default()).unwrap();
+        let bounds: Vec<(i64, i64)> = analysis.silence_segments.iter().map(|s| (s.start_ms, s.end_ms)).collect();
+        assert_eq!(bounds, vec![(1000, 1500), (3700, 4100)]);
+        assert_eq!(analysis.duration_ms, 4100);
+    }
+
+    #[test]
+    fn test_detect_silence_adaptive() {
+        // 静かな番組（-44dB）: 固定の -40dB では全体が無音になる
+        let data = pcm(&[(2.0, 200), (0.5, 0), (2.0, 200), (0.5, 0), (1.0, 200)]);
+        let fixed = detect_silence(&mut data.as_slice(), MONO_8K, SilenceParams::default()).unwrap();
+        assert_eq!(fixed.silence_segments.len(), 1);
+
+        let params = SilenceParams {
+            adaptive: true,
+            ..SilenceParams::default()
+        };
+        let analysis = detect_silence(&mut data.as_slice(), MONO_8K, params).unwrap();
+        let bounds: Vec<(i64, i64)> = analysis.silence_segments.iter().map(|s| (s.start_ms, s.end_ms)).collect();
+        assert_eq!(bounds, vec![(2000, 2500), (4500, 5000)]);
+        assert_eq!(analysis.duration_ms, 6000);
+        let report = analysis.threshold.unwrap();
+        assert_eq!(report.noise_floor_db, Some(-99.0));
+        assert_eq!(report.noise_db, -84.0);
+    }
+
+    #[test]
+    fn test_detect_silence_range() {
+        // 0.2s の無音は d=0.3 では検出されず、d=0.1 で範囲を限って再解析すると見つかる
+        let data = pcm(&[(1.0, 10000), (0.2, 0), (1.0, 10000), (0.5, 0), (1.0, 10000)]);
+        let analysis = detect_silence(&mut data.as_slice(), MONO_8K, SilenceParams::default()).unwrap();
+        assert_eq!(analysis.silence_segments.len(), 1);
+
+        let params = SilenceParams {
+            min_duration_sec: 0.1,
+            ..SilenceParams::default()
+        };
+        let mut file = std::io::Cursor::new(data);
+        let found = detect_silence_range(&mut file, 0, MONO_8K, 500, 2000, params).unwrap();
+        let bounds: Vec<(i64, i64)> = found.iter().map(|s| (s.start_ms, s.end_ms)).collect();
+        assert_eq!(bounds, vec![(1000, 1200)]);
+    }
+
+    #[test]
+    fn test_read_wav_header() {
+        let data = pcm(&[(1.0, 10000), (0.5, 0), (0.5, 10000)]);
+        let mut wav = Vec::new();
+        wav.extend(b"RIFF");
+        wav.extend(0u32.to_le_bytes());
+        wav.extend(b"WAVE");
+        wav.extend(b"LIST");
+        wav.extend(3u32.to_le_bytes());
+        wav.extend([0u8; 4]); // 奇数長 + パディング
+        wav.extend(b"fmt ");
+        wav.extend(16u32.to_le_bytes());
+        wav.extend(1u16.to_le_bytes());
+        wav.extend(1u16.to_le_bytes());
+        wav.extend(8000u32.to_le_bytes());
+        wav.extend(16000u32.to_le_bytes());
+        wav.extend(2u16.to_le_bytes());
+        wav.extend(16u16.to_le_bytes());
+        wav.extend(b"data");
+        wav.extend(u32::MAX.to_le_bytes());
+        wav.extend(&data);
+
+        let mut reader = wav.as_slice();
+        let (format, length) = read_wav_header(&mut reader).unwrap();
+        assert_eq!(format, MONO_8K);
+        assert_eq!(length, None);
+        let analysis = detect_silence(&mut reader, format, SilenceParams::default()).unwrap();
+        assert_eq!(analysis.silence_segments.len(), 1);
+        assert_eq!(analysis.silence_segments[0].start_ms, 1000);
+
+        assert!(read_wav_header(&mut &b"RIFX\0\0\0\0WAVE"[..]).is_err());
+    }
+
+    /// fmt チャンクの長さ size のWAVヘッダ（16バイトを超える部分は0埋め）
+    fn wav_header_with_fmt_size(size: u32) -> Vec<u8> {
+        let mut wav = Vec::new();
+        wav.extend(b"RIFF");
+        wav.extend(0u32.to_le_bytes());
+        wav.extend(b"WAVE");
+        wav.extend(b"fmt ");
+        wav.extend(size.to_le_bytes());
+        wav.extend(1u16.to_le_bytes());
+        wav.extend(1u16.to_le_bytes());
+        wav.extend(8000u32.to_le_bytes());
+        wav.extend(16000u32.to_le_bytes());
+        wav.extend(2u16.to_le_bytes());
+        wav.extend(16u16.to_le_bytes());
+        wav.resize(wav.len() + (size as usize).saturating_sub(16).min(64) + (size as usize & 1), 0);
+        wav.extend(b"data");
+        wav.extend(4u32.to_le_bytes());
+        wav
+    }
+
+    #[test]
+    fn test_read_wav_header_fmt_size() {
+        // cbSize 付き（18バイト）、奇数長（パディング付き）、読む長さを超える fmt チャンクは読み飛ばす
+        for size in [18, 41, 60] {
+            let wav = wav_header_with_fmt_size(size);
+            let (format, length) = read_wav_header(&mut wav.as_slice()).unwrap();
+            assert_eq!((format, length), (MONO_8K, Some(4)), "fmt size {}", size);
+        }
+
+        // 短すぎる・長すぎる fmt チャンクは長さを信用せず拒否する
+        assert!(read_wav_header(&mut wav_header_with_fmt_size(14).as_slice()).is_err());
+        assert!(read_wav_header(&mut wav_header_with_fmt_size(u32::MAX).as_slice()).is_err());
+    }
+}
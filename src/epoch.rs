@@ -0,0 +1,203 @@
+// PTSの不連続（ラップアラウンド・リセット・跳躍）による時刻の分割と補正
+//
+// MPEG-TS の録画ではチューナーの瞬断などでPTSがリセットされ、silencedetect の時刻が巻き戻ったり
+// 数時間先に跳んだりする。ログの順に連続する区間（エポック）に分割し、各エポックの時刻に
+// オフセットを加えてファイル先頭からの時刻に揃える。
+
+use serde::Serialize;
+
+use crate::SilenceSegment;
+
+const PTS_WRAP_MS: i64 = 95_443_718; // 33bit PTS（90kHz）の周期 2^33 / 90000 秒
+const WRAP_TOLERANCE_MS: i64 = 600_000; // ラップアラウンドとみなす巻き戻り幅の誤差
+const RESET_MIN_MS: i64 = 10_000; // これ以上の巻き戻りをリセットとみなす（それ未満はログの異常として扱う）
+const LEAP_MIN_MS: i64 = 3_600_000; // これ以上の無音区間の間隔を跳躍とみなす
+const LEAP_IMPLAUSIBLE_MS: i64 = PTS_WRAP_MS / 2; // 入力の長さが分からない場合に跳躍とみなす間隔（約13時間）
+
+/// 不連続の種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Discontinuity {
+    /// 33bit PTS の周期による巻き戻り（補正は正確）
+    Wraparound,
+    /// 時刻の巻き戻り
+    Reset,
+    /// 時刻の前方への跳躍
+    Leap,
+}
+
+/// 時刻が連続する区間
+#[derive(Debug, Clone, Serialize)]
+pub struct Epoch {
+    /// 補正後の時刻での範囲（最初の無音区間の開始から最後の無音区間の終了まで）
+    pub start_ms: i64,
+    pub end_ms: i64,
+    /// ログの時刻に加えるオフセット
+    pub offset_ms: i64,
+    /// このエポックの直前の不連続（最初のエポックは None）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub discontinuity: Option<Discontinuity>,
+    #[serde(skip)]
+    pub first: usize,
+    #[serde(skip)]
+    pub last: usize,
+}
+
+/// 直前の無音区間との間の不連続を判定
+fn classify(prev: &SilenceSegment, curr: &SilenceSegment, duration_ms: Option<i64>) -> Option<Discontinuity> {
+    let step = curr.start_ms - prev.end_ms;
+    if step < 0 && curr.start_ms < prev.start_ms - RESET_MIN_MS {
+        if (step + PTS_WRAP_MS).abs() < WRAP_TOLERANCE_MS {
+            return Some(Discontinuity::Wraparound);
+        }
+        return Some(Discontinuity::Reset);
+    }
+    // 入力の長さが分かる場合、その範囲内の長い間隔は無音のない本編として扱う
+    // 長さが分からない場合（CSV等）は、1時間程度の間隔は無音のない本編と区別できないため、
+    // 録画としてありえない（PTSの半周期以上の）間隔のみを跳躍とみなす
+    let is_leap = match duration_ms {
+        Some(d) => curr.start_ms > d && (prev.end_ms <= d || step >= LEAP_MIN_MS),
+        None => step >= LEAP_IMPLAUSIBLE_MS,
+    };
+    is_leap.then_some(Discontinuity::Leap)
+}
+
+/// 無音区間の間隔の中央値（不連続の前後の間隔の推定に使う）
+fn median_gap_ms(segments: &[SilenceSegment]) -> i64 {
+    let mut gaps: Vec<i64> = segments
+        .windows(2)
+        .map(|pair| pair[1].start_ms - pair[0].end_ms)
+        .filter(|&gap| (0..LEAP_MIN_MS).contains(&gap))
+        .collect();
+    if gaps.is_empty() {
+        return 0;
+    }
+    gaps.sort_unstable();
+    gaps[gaps.len() / 2]
+}
+
+/// ログの順の無音区間をエポックに分割し、オフセットを求める
+/// - ラップアラウンド: PTSの周期を加える
+/// - リセット・跳躍: 不連続の前後の間隔を無音区間の間隔の中央値と推定する
+pub fn split_epochs(segments: &[SilenceSegment], duration_ms: Option<i64>) -> Vec<Epoch> {
+    if segments.is_empty() {
+        return Vec::new();
+    }
+    let median_gap = median_gap_ms(segments);
+    let mut epochs = vec![Epoch {
+        start_ms: segments[0].start_ms,
+        end_ms: segments[0].end_ms,
+        offset_ms: 0,
+        discontinuity: None,
+        first: 0,
+        last: 0,
+    }];
+
+    for (i, pair) in segments.windows(2).enumerate() {
+        let (prev, curr) = (&pair[0], &pair[1]);
+        let epoch = epochs.last_mut().unwrap();
+        let Some(kind) = classify(prev, curr, duration_ms) else {
+            epoch.last = i + 1;
+            epoch.end_ms = epoch.end_ms.max(curr.end_ms + epoch.offset_ms);
+            continue;
+        };
+
+        let prev_end = prev.end_ms + epoch.offset_ms;
+        let offset_ms = match kind {
+            Discontinuity::Wraparound => epoch.offset_ms + PTS_WRAP_MS,
+            Discontinuity::Reset | Discontinuity::Leap => prev_end + median_gap - curr.start_ms,
+        };
+        epochs.push(Epoch {
+            start_ms: curr.start_ms + offset_ms,
+            end_ms: curr.end_ms + offset_ms,
+            offset_ms,
+            discontinuity: Some(kind),
+            first: i + 1,
+            last: i + 1,
+        });
+    }
+    epochs
+}
+
+/// 各エポックのオフセットを加えた無音区間
+pub fn apply_offsets(segments: &[SilenceSegment], epochs: &[Epoch]) -> Vec<SilenceSegment> {
+    epochs
+        .iter()
+        .flat_map(|epoch| {
+            segments[epoch.first..=epoch.last].iter().map(|s| SilenceSegment {
+                start_ms: s.start_ms + epoch.offset_ms,
+                end_ms: s.end_ms + epoch.offset_ms,
+                duration_ms: s.duration_ms,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn silence(start_ms: i64, end_ms: i64) -> SilenceSegment {
+        SilenceSegment {
+            start_ms,
+            end_ms,
+            duration_ms: end_ms - start_ms,
+        }
+    }
+
+    #[test]
+    fn test_continuous_log_has_one_epoch() {
+        let segments = vec![silence(1000, 1500), silence(16000, 16500), silence(31000, 31500)];
+        let epochs = split_epochs(&segments, Some(60000));
+        assert_eq!(epochs.len(), 1);
+        assert_eq!((epochs[0].start_ms, epochs[0].end_ms, epochs[0].offset_ms), (1000, 31500, 0));
+    }
+
+    #[test]
+    fn test_split_epochs() {
+        let segments = vec![
+            silence(100000, 100500),
+            silence(115000, 115500),
+            // 0付近へのリセット
+            silence(4000, 4500),
+            silence(19000, 19500),
+            // ラップアラウンド
+            silence(19000 + 115000 - PTS_WRAP_MS, 19500 + 115000 - PTS_WRAP_MS),
+            // 数時間先への跳躍
+            silence(50_000_000, 50_000_500),
+        ];
+        let epochs = split_epochs(&segments, None);
+        let kinds: Vec<Option<Discontinuity>> = epochs.iter().map(|e| e.discontinuity).collect();
+        assert_eq!(
+            kinds,
+            vec![None, Some(Discontinuity::Reset), Some(Discontinuity::Wraparound), Some(Discontinuity::Leap)]
+        );
+
+        let mapped: Vec<i64> = apply_offsets(&segments, &epochs).iter().map(|s| s.start_ms).collect();
+        // リセット: 跳躍と同じく間隔の中央値（14500）で接続
+        assert_eq!(mapped[2], 115500 + 14500);
+        assert_eq!(mapped[3] - mapped[2], 15000);
+        // ラップアラウンド: 周期を加えると元の時刻の連続性が保たれる
+        assert_eq!(mapped[4] - mapped[3], 115000);
+        // 跳躍: 間隔の中央値（14500）で接続
+        assert_eq!(mapped[5], mapped[4] + 500 + 14500);
+        assert!(mapped.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn test_long_gap_within_duration_is_not_leap() {
+        // 70分間無音区間のない本編（入力の長さの範囲内）
+        let segments = vec![silence(1000, 1500), silence(4_201_500, 4_202_000), silence(4_217_000, 4_217_500)];
+        let epochs = split_epochs(&segments, Some(5_400_000));
+        assert_eq!(epochs.len(), 1);
+        assert_eq!((epochs[0].start_ms, epochs[0].end_ms, epochs[0].offset_ms), (1000, 4_217_500, 0));
+
+        // 長さが分からない場合も、ありえない長さの間隔でなければ跳躍とみなさない
+        let epochs = split_epochs(&segments, None);
+        assert_eq!(epochs.len(), 1);
+        let far = vec![silence(1000, 1500), silence(LEAP_IMPLAUSIBLE_MS + 2000, LEAP_IMPLAUSIBLE_MS + 2500)];
+        let epochs = split_epochs(&far, None);
+        assert_eq!(epochs.len(), 2);
+        assert_eq!(epochs[1].discontinuity, Some(Discontinuity::Leap));
+    }
+}
@@ -0,0 +1,575 @@
+// 検出結果と正解（手作業で修正したCM区間）の比較（cm-detector eval）
+//
+// 正解・検出結果はどちらも cm-detector のJSON出力（cm_blocks）か、{start_ms, end_ms} の配列で読み込む。
+// - 重なり: CM区間の時間（ミリ秒）の重なりから適合率・再現率・F1 を求める
+// - 境界: CMブロックの開始点同士・終了点同士を許容範囲内で1対1に対応させ、適合率・再現率・F1 と平均誤差を求める
+// - ブロック: 検出結果と重ならない正解のブロック（検出漏れ）と、正解と重ならない検出結果のブロック（誤検出）
+
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::CmBlock;
+
+const BOUNDARY_TOLERANCE_MS: i64 = 1000; // 境界を対応させる距離の上限（デフォルト）
+
+/// CM区間
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Interval {
+    pub start_ms: i64,
+    pub end_ms: i64,
+}
+
+impl From<&CmBlock> for Interval {
+    fn from(block: &CmBlock) -> Self {
+        Interval {
+            start_ms: block.start_ms,
+            end_ms: block.end_ms,
+        }
+    }
+}
+
+/// 正解・検出結果のファイルの形式
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum IntervalFile {
+    /// cm-detector のJSON出力（またはそれを手作業で修正したもの）
+    Output { cm_blocks: Vec<Interval> },
+    /// CM区間の配列
+    List(Vec<Interval>),
+}
+
+/// CM区間を読み込む（時刻順に並べ、重なる区間は統合する）
+pub fn read_intervals(path: &str) -> Result<Vec<Interval>, String> {
+    let content = fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let file: IntervalFile = serde_json::from_str(&content)
+        .map_err(|e| format!("Invalid CM intervals {} (expected cm_blocks or a list of start_ms/end_ms): {}", path, e))?;
+    let intervals = match file {
+        IntervalFile::Output { cm_blocks } => cm_blocks,
+        IntervalFile::List(intervals) => intervals,
+    };
+    Ok(normalize(intervals))
+}
+
+/// 時刻順に並べ、空の区間を除き、重なる区間を統合する（接するだけの区間は別のブロックとして残す）
+pub fn normalize(mut intervals: Vec<Interval>) -> Vec<Interval> {
+    intervals.retain(|i| i.end_ms > i.start_ms);
+    intervals.sort_by_key(|i| i.start_ms);
+    let mut merged: Vec<Interval> = Vec::new();
+    for interval in intervals {
+        match merged.last_mut() {
+            Some(last) if interval.start_ms < last.end_ms => last.end_ms = last.end_ms.max(interval.end_ms),
+            _ => merged.push(interval),
+        }
+    }
+    merged
+}
+
+/// 時間の重なりによる集計（ミリ秒）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct OverlapCounts {
+    pub truth_ms: i64,
+    pub pred_ms: i64,
+    pub overlap_ms: i64,
+}
+
+impl OverlapCounts {
+    /// truth / pred は normalize 済みの区間
+    pub fn new(truth: &[Interval], pred: &[Interval]) -> Self {
+        let total = |intervals: &[Interval]| intervals.iter().map(|i| i.end_ms - i.start_ms).sum();
+        let mut overlap_ms = 0;
+        let (mut t, mut p) = (0, 0);
+        while t < truth.len() && p < pred.len() {
+            let start = truth[t].start_ms.max(pred[p].start_ms);
+            let end = truth[t].end_ms.min(pred[p].end_ms);
+            overlap_ms += (end - start).max(0);
+            if truth[t].end_ms < pred[p].end_ms {
+                t += 1;
+            } else {
+                p += 1;
+            }
+        }
+        OverlapCounts {
+            truth_ms: total(truth),
+            pred_ms: total(pred),
+            overlap_ms,
+        }
+    }
+
+    pub fn add(&mut self, other: &OverlapCounts) {
+        self.truth_ms += other.truth_ms;
+        self.pred_ms += other.pred_ms;
+        self.overlap_ms += other.overlap_ms;
+    }
+
+    pub fn precision(&self) -> f64 {
+        ratio(self.overlap_ms, self.pred_ms, self.truth_ms)
+    }
+
+    pub fn recall(&self) -> f64 {
+        ratio(self.overlap_ms, self.truth_ms, self.pred_ms)
+    }
+
+    pub fn f1(&self) -> f64 {
+        ratio(2 * self.overlap_ms, self.truth_ms + self.pred_ms, 0)
+    }
+}
+
+/// 境界の対応の集計
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct BoundaryCounts {
+    pub truth: usize,
+    pub pred: usize,
+    pub matched: usize,
+    /// 対応した境界の誤差の合計
+    #[serde(skip)]
+    pub error_ms: i64,
+}
+
+impl BoundaryCounts {
+    /// truth / pred は normalize 済みの区間。開始点は開始点と、終了点は終了点と、距離の近い組から順に1対1で対応させる
+    pub fn new(truth: &[Interval], pred: &[Interval], tolerance_ms: i64) -> Self {
+        // (時刻, 終了点か)
+        let points = |intervals: &[Interval]| -> Vec<(i64, bool)> {
+            intervals.iter().flat_map(|i| [(i.start_ms, false), (i.end_ms, true)]).collect()
+        };
+        let truth_points = points(truth);
+        let pred_points = points(pred);
+
+        let mut pairs: Vec<(i64, usize, usize)> = Vec::new();
+        for (t, (truth_ms, truth_is_end)) in truth_points.iter().enumerate() {
+            for (p, (pred_ms, pred_is_end)) in pred_points.iter().enumerate() {
+                let distance = (truth_ms - pred_ms).abs();
+                if truth_is_end == pred_is_end && distance <= tolerance_ms {
+                    pairs.push((distance, t, p));
+                }
+            }
+        }
+        pairs.sort();
+
+        let mut truth_used = vec![false; truth_points.len()];
+        let mut pred_used = vec![false; pred_points.len()];
+        let mut counts = BoundaryCounts {
+            truth: truth_points.len(),
+            pred: pred_points.len(),
+            ..BoundaryCounts::default()
+        };
+        for (distance, t, p) in pairs {
+            if !truth_used[t] && !pred_used[p] {
+                truth_used[t] = true;
+                pred_used[p] = true;
+                counts.matched += 1;
+                counts.error_ms += distance;
+            }
+        }
+        counts
+    }
+
+    pub fn add(&mut self, other: &BoundaryCounts) {
+        self.truth += other.truth;
+        self.pred += other.pred;
+        self.matched += other.matched;
+        self.error_ms += other.error_ms;
+    }
+
+    pub fn precision(&self) -> f64 {
+        ratio(self.matched as i64, self.pred as i64, self.truth as i64)
+    }
+
+    pub fn recall(&self) -> f64 {
+        ratio(self.matched as i64, self.truth as i64, self.pred as i64)
+    }
+
+    pub fn f1(&self) -> f64 {
+        ratio(2 * self.matched as i64, (self.truth + self.pred) as i64, 0)
+    }
+
+    /// 対応した境界の平均誤差（対応がなければ None）
+    pub fn mean_error_ms(&self) -> Option<f64> {
+        (self.matched > 0).then(|| self.error_ms as f64 / self.matched as f64)
+    }
+}
+
+/// 他方のどの区間とも重ならない区間
+fn unmatched(intervals: &[Interval], others: &[Interval]) -> Vec<Interval> {
+    intervals
+        .iter()
+        .filter(|i| !others.iter().any(|o| i.start_ms < o.end_ms && o.start_ms < i.end_ms))
+        .copied()
+        .collect()
+}
+
+/// 分母が0の場合、もう一方（適合率なら正解、再現率なら検出結果）も空なら誤りがないものとして 1.0、
+/// 空でなければ 0.0 とする（正解があるのに何も検出しなかった場合に適合率を 1.0 としない）
+fn ratio(numerator: i64, denominator: i64, other: i64) -> f64 {
+    if denominator == 0 {
+        if other == 0 { 1.0 } else { 0.0 }
+    } else {
+        numerator as f64 / denominator as f64
+    }
+}
+
+/// レポートの形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum ReportFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+impl ReportFormat {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "text" => Some(ReportFormat::Text),
+            "json" => Some(ReportFormat::Json),
+            _ => None,
+        }
+    }
+}
+
+/// eval サブコマンドの引数
+#[derive(Debug)]
+struct EvalArgs {
+    /// 正解のファイル、またはディレクトリ
+    truth: String,
+    /// 検出結果のファイル、または正解と同じファイル名で検出結果を置いたディレクトリ
+    pred: String,
+    boundary_tolerance_ms: i64,
+    format: ReportFormat,
+}
+
+fn parse_eval_args(args: impl IntoIterator<Item = String>) -> Result<EvalArgs, String> {
+    let mut truth = None;
+    let mut pred = None;
+    let mut boundary_tolerance_ms = BOUNDARY_TOLERANCE_MS;
+    let mut format = ReportFormat::default();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--truth" => truth = Some(args.next().ok_or("--truth requires a value")?),
+            "--pred" => pred = Some(args.next().ok_or("--pred requires a value")?),
+            "--boundary-tolerance" => {
+                let value = args.next().ok_or("--boundary-tolerance requires a value")?;
+                boundary_tolerance_ms = value
+                    .parse()
+                    .ok()
+                    .filter(|ms| *ms >= 0)
+                    .ok_or_else(|| format!("Invalid boundary tolerance: {}", value))?;
+            }
+            "--format" => {
+                let value = args.next().ok_or("--format requires a value")?;
+                format = ReportFormat::parse(&value)
+                    .ok_or_else(|| format!("Unknown report format: {} (expected text or json)", value))?;
+            }
+            _ => return Err(format!("Unknown argument: {}", arg)),
+        }
+    }
+    Ok(EvalArgs {
+        truth: truth.ok_or("eval requires --truth")?,
+        pred: pred.ok_or("eval requires --pred")?,
+        boundary_tolerance_ms,
+        format,
+    })
+}
+
+/// 適合率・再現率・F1
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Scores {
+    pub precision: f64,
+    pub recall: f64,
+    pub f1: f64,
+}
+
+impl Scores {
+    fn new(precision: f64, recall: f64, f1: f64) -> Self {
+        let round = |value: f64| (value * 10000.0).round() / 10000.0;
+        Scores {
+            precision: round(precision),
+            recall: round(recall),
+            f1: round(f1),
+        }
+    }
+}
+
+/// 時間の重なりによる評価のJSON出力
+#[derive(Debug, Clone, Serialize)]
+pub struct OverlapReport {
+    #[serde(flatten)]
+    pub counts: OverlapCounts,
+    #[serde(flatten)]
+    pub scores: Scores,
+}
+
+impl From<OverlapCounts> for OverlapReport {
+    fn from(counts: OverlapCounts) -> Self {
+        OverlapReport {
+            counts,
+            scores: Scores::new(counts.precision(), counts.recall(), counts.f1()),
+        }
+    }
+}
+
+/// 境界単位の評価のJSON出力
+#[derive(Debug, Clone, Serialize)]
+pub struct BoundaryReport {
+    #[serde(flatten)]
+    pub counts: BoundaryCounts,
+    #[serde(flatten)]
+    pub scores: Scores,
+    pub mean_error_ms: Option<f64>,
+}
+
+impl From<BoundaryCounts> for BoundaryReport {
+    fn from(counts: BoundaryCounts) -> Self {
+        BoundaryReport {
+            counts,
+            scores: Scores::new(counts.precision(), counts.recall(), counts.f1()),
+            mean_error_ms: counts.mean_error_ms().map(|ms| ms.round()),
+        }
+    }
+}
+
+/// ファイルごとの評価
+#[derive(Debug, Clone, Serialize)]
+pub struct FileReport {
+    pub name: String,
+    pub overlap: OverlapReport,
+    pub boundary: BoundaryReport,
+    /// 検出漏れ（検出結果と重ならない正解のブロック）
+    pub missed_blocks: Vec<Interval>,
+    /// 誤検出（正解と重ならない検出結果のブロック）
+    pub extra_blocks: Vec<Interval>,
+}
+
+/// 全ファイルの評価
+#[derive(Debug, Clone, Serialize)]
+pub struct AggregateReport {
+    pub files: usize,
+    pub overlap: OverlapReport,
+    pub boundary: BoundaryReport,
+    pub missed_blocks: usize,
+    pub extra_blocks: usize,
+}
+
+/// eval の出力
+#[derive(Debug, Clone, Serialize)]
+pub struct EvalReport {
+    pub files: Vec<FileReport>,
+    pub aggregate: AggregateReport,
+}
+
+/// 1ファイル分の正解と検出結果を比較
+pub fn evaluate_file(name: &str, truth: &[Interval], pred: &[Interval], boundary_tolerance_ms: i64) -> FileReport {
+    FileReport {
+        name: name.to_string(),
+        overlap: OverlapCounts::new(truth, pred).into(),
+        boundary: BoundaryCounts::new(truth, pred, boundary_tolerance_ms).into(),
+        missed_blocks: unmatched(truth, pred),
+        extra_blocks: unmatched(pred, truth),
+    }
+}
+
+/// ファイルごとの評価を集計（重なりの時間・境界の件数を合算してから各指標を求める）
+pub fn aggregate(files: Vec<FileReport>) -> EvalReport {
+    let mut overlap = OverlapCounts::default();
+    let mut boundary = BoundaryCounts::default();
+    for file in &files {
+        overlap.add(&file.overlap.counts);
+        boundary.add(&file.boundary.counts);
+    }
+    let aggregate = AggregateReport {
+        files: files.len(),
+        overlap: overlap.into(),
+        boundary: boundary.into(),
+        missed_blocks: files.iter().map(|f| f.missed_blocks.len()).sum(),
+        extra_blocks: files.iter().map(|f| f.extra_blocks.len()).sum(),
+    };
+    EvalReport { files, aggregate }
+}
+
+/// 比較する (名前, 正解, 検出結果) の組
+/// --truth がディレクトリの場合は、その中の *.json と --pred の同名のファイルを組にする
+fn collect_pairs(truth: &str, pred: &str) -> Result<Vec<(String, String, String)>, String> {
+    let truth_path = Path::new(truth);
+    if !truth_path.is_dir() {
+        return Ok(vec![(truth.to_string(), truth.to_string(), pred.to_string())]);
+    }
+    let pred_dir = Path::new(pred);
+    if !pred_dir.is_dir() {
+        return Err(format!("--pred must be a directory when --truth is a directory: {}", pred));
+    }
+    let entries = fs::read_dir(truth_path).map_err(|e| format!("Failed to read {}: {}", truth, e))?;
+    let mut names: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .filter(|name| name.ends_with(".json"))
+        .collect();
+    names.sort();
+
+    let mut pairs = Vec::new();
+    for name in names {
+        let pred_path = pred_dir.join(&name);
+        if !pred_path.exists() {
+            return Err(format!("No prediction for {} in {}", name, pred));
+        }
+        pairs.push((
+            name.clone(),
+            truth_path.join(&name).to_string_lossy().into_owned(),
+            pred_path.to_string_lossy().into_owned(),
+        ));
+    }
+    if pairs.is_empty() {
+        return Err(format!("No truth files (*.json) in {}", truth));
+    }
+    Ok(pairs)
+}
+
+fn format_interval(interval: &Interval) -> String {
+    format!("{:.1}-{:.1}s", interval.start_ms as f64 / 1000.0, interval.end_ms as f64 / 1000.0)
+}
+
+fn format_scores(overlap: &OverlapReport, boundary: &BoundaryReport) -> String {
+    let mean_error = match boundary.mean_error_ms {
+        Some(ms) => format!("{:.0}ms", ms),
+        None => "-".to_string(),
+    };
+    format!(
+        "  overlap   P {:.3}  R {:.3}  F1 {:.3}  (truth {:.1}s, pred {:.1}s, overlap {:.1}s)\n  boundary  P {:.3}  R {:.3}  F1 {:.3}  mean error {} ({}/{} matched)",
+        overlap.scores.precision,
+        overlap.scores.recall,
+        overlap.scores.f1,
+        overlap.counts.truth_ms as f64 / 1000.0,
+        overlap.counts.pred_ms as f64 / 1000.0,
+        overlap.counts.overlap_ms as f64 / 1000.0,
+        boundary.scores.precision,
+        boundary.scores.recall,
+        boundary.scores.f1,
+        mean_error,
+        boundary.counts.matched,
+        boundary.counts.truth
+    )
+}
+
+/// テキストのレポート
+fn format_text(report: &EvalReport) -> String {
+    let mut lines = Vec::new();
+    for file in &report.files {
+        lines.push(file.name.clone());
+        lines.push(format_scores(&file.overlap, &file.boundary));
+        for block in &file.missed_blocks {
+            lines.push(format!("  missed    {}", format_interval(block)));
+        }
+        for block in &file.extra_blocks {
+            lines.push(format!("  extra     {}", format_interval(block)));
+        }
+    }
+    let aggregate = &report.aggregate;
+    lines.push(format!("TOTAL ({} files)", aggregate.files));
+    lines.push(format_scores(&aggregate.overlap, &aggregate.boundary));
+    lines.push(format!(
+        "  blocks    {} missed, {} extra",
+        aggregate.missed_blocks, aggregate.extra_blocks
+    ));
+    lines.join("\n")
+}
+
+/// eval サブコマンド
+pub fn run(args: impl IntoIterator<Item = String>) -> Result<(), String> {
+    let args = parse_eval_args(args)?;
+    let mut files = Vec::new();
+    for (name, truth, pred) in collect_pairs(&args.truth, &args.pred)? {
+        let truth = read_intervals(&truth)?;
+        let pred = read_intervals(&pred)?;
+        files.push(evaluate_file(&name, &truth, &pred, args.boundary_tolerance_ms));
+    }
+    let report = aggregate(files);
+    match args.format {
+        ReportFormat::Text => println!("{}", format_text(&report)),
+        ReportFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(&report).expect("Failed to serialize JSON"))
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn interval(start_ms: i64, end_ms: i64) -> Interval {
+        Interval { start_ms, end_ms }
+    }
+
+    #[test]
+    fn test_overlap_counts() {
+        let truth = vec![interval(0, 60000), interval(600000, 690000)];
+        // 1つ目は10秒遅れ、2つ目は検出漏れ、余分なブロックが1つ
+        let pred = vec![interval(10000, 70000), interval(300000, 330000)];
+        let counts = OverlapCounts::new(&truth, &pred);
+        assert_eq!(counts, OverlapCounts { truth_ms: 150000, pred_ms: 90000, overlap_ms: 50000 });
+        assert!((counts.precision() - 50.0 / 90.0).abs() < 1e-9);
+        assert!((counts.recall() - 50.0 / 150.0).abs() < 1e-9);
+        assert!((counts.f1() - 100.0 / 240.0).abs() < 1e-9);
+
+        let empty = OverlapCounts::new(&[], &[]);
+        assert_eq!((empty.precision(), empty.recall(), empty.f1()), (1.0, 1.0, 1.0));
+        // 何も検出しなかった場合、適合率も 0
+        let nothing = OverlapCounts::new(&truth, &[]);
+        assert_eq!((nothing.precision(), nothing.recall(), nothing.f1()), (0.0, 0.0, 0.0));
+        let no_truth = BoundaryCounts::new(&[], &pred, 1000);
+        assert_eq!((no_truth.precision(), no_truth.recall(), no_truth.f1()), (0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_interval_file() {
+        let output: IntervalFile =
+            serde_json::from_str(r#"{"input_file": "a.log", "cm_blocks": [{"start_ms": 0, "end_ms": 60000, "duration_sec": 60.0}]}"#)
+                .unwrap();
+        assert!(matches!(output, IntervalFile::Output { cm_blocks } if cm_blocks == vec![interval(0, 60000)]));
+        let list: IntervalFile = serde_json::from_str(r#"[{"start_ms": 0, "end_ms": 1000}]"#).unwrap();
+        assert!(matches!(list, IntervalFile::List(_)));
+
+        let merged = normalize(vec![interval(30000, 90000), interval(0, 45000), interval(100000, 100000)]);
+        assert_eq!(merged, vec![interval(0, 90000)]);
+        // 接するだけの区間（連続するCMブロック）は統合しない
+        let adjacent = normalize(vec![interval(60000, 120000), interval(0, 60000)]);
+        assert_eq!(adjacent, vec![interval(0, 60000), interval(60000, 120000)]);
+    }
+
+    #[test]
+    fn test_evaluate_file() {
+        let truth = vec![interval(0, 60000), interval(600000, 690000)];
+        let pred = vec![interval(400, 59000), interval(300000, 330000)];
+        let report = evaluate_file("ep01.json", &truth, &pred, 1000);
+
+        // 境界: 0/400 と 60000/59000 が対応（誤差 400ms / 1000ms）
+        assert_eq!(
+            report.boundary.counts,
+            BoundaryCounts { truth: 4, pred: 4, matched: 2, error_ms: 1400 }
+        );
+        assert_eq!(report.boundary.mean_error_ms, Some(700.0));
+        assert_eq!(report.boundary.scores.f1, 0.5);
+        assert_eq!(report.missed_blocks, vec![interval(600000, 690000)]);
+        assert_eq!(report.extra_blocks, vec![interval(300000, 330000)]);
+
+        // 許容範囲を狭めると 60000/59000 は対応しない
+        let strict = BoundaryCounts::new(&truth, &pred, 500);
+        assert_eq!((strict.matched, strict.error_ms), (1, 400));
+
+        // 開始点と終了点は対応させない（正解の終了点 60000 と検出結果の開始点 60500 は近いが対応しない）
+        let crossed = BoundaryCounts::new(&[interval(0, 60000)], &[interval(60500, 90000)], 1000);
+        assert_eq!(crossed.matched, 0);
+
+        // 集計は件数を合算してから求める
+        let perfect = evaluate_file("ep02.json", &truth, &truth, 1000);
+        let report = aggregate(vec![report, perfect]);
+        assert_eq!(report.aggregate.files, 2);
+        assert_eq!(report.aggregate.boundary.counts.matched, 6);
+        assert_eq!(report.aggregate.boundary.scores.recall, 0.75);
+        assert_eq!((report.aggregate.missed_blocks, report.aggregate.extra_blocks), (1, 1));
+        let text = format_text(&report);
+        assert!(text.contains("  missed    600.0-690.0s"));
+        assert!(text.contains("TOTAL (2 files)"));
+    }
+}
@@ -0,0 +1,336 @@
+// 複数の信号による境界の根拠の統合
+//
+// 各入力（無音・黒画面・シーンチェンジ・ラウドネスの段差・チャンネルレイアウトの変化）は
+// BoundaryEvidence として境界の候補範囲と重みを提供し、fuse で1つの境界候補リストにまとめる。
+// チェーン検出は統合された境界候補を無音区間と同様に扱う（範囲のみを使い、重みは使わない）。
+// 重みの合計（score）は境界を支持する根拠の説明として出力にのみ使う。
+
+use serde::Serialize;
+
+use crate::loudness::{self, LoudnessSample};
+use crate::{
+    BLACK_SILENCE_WINDOW_MS, BlackSegment, CHANNEL_CHANGE_WINDOW_MS, ChannelLayoutChange, Range,
+    SCENE_CUT_WINDOW_MS, SilenceSegment,
+};
+
+const SILENCE_WEIGHT: f64 = 1.0;
+const BLACK_WEIGHT: f64 = 0.5;
+const SCENE_CUT_WEIGHT: f64 = 0.5;
+const LOUDNESS_STEP_WEIGHT: f64 = 0.25;
+const CHANNEL_CHANGE_WEIGHT: f64 = 1.0;
+const LOUDNESS_STEP_LU: f64 = 6.0; // 境界とみなすラウドネスの段差
+const LOUDNESS_STEP_WINDOW_MS: i64 = 1000; // 境界とラウドネスの段差を同じ境界とみなす距離
+
+/// 境界の根拠となる信号の種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Signal {
+    Silence,
+    Black,
+    SceneCut,
+    LoudnessStep,
+    ChannelChange,
+}
+
+/// 境界の根拠を提供する入力
+pub trait BoundaryEvidence {
+    fn signal(&self) -> Signal;
+
+    /// 境界候補としての重み（出力の score に加算する。チェーン検出には影響しない）
+    fn weight(&self) -> f64;
+
+    /// 境界の候補範囲（時刻順）
+    fn ranges(&self) -> Vec<Range>;
+
+    /// 他の境界候補を支持するとみなす距離
+    fn window_ms(&self) -> i64;
+
+    /// 支持する境界がなくても単独で境界候補になるか
+    fn standalone(&self) -> bool {
+        false
+    }
+
+    /// 支持する境界の範囲を、この信号の範囲（中心点に最も近いもの）に絞り込むか
+    fn refines(&self) -> bool {
+        false
+    }
+}
+
+/// 統合された境界候補
+#[derive(Debug, Clone)]
+pub struct FusedBoundary {
+    pub range: Range,
+    /// 支持する信号の重みの合計（出力用。チェーン検出では使わない）
+    pub score: f64,
+    pub signals: Vec<Signal>,
+}
+
+impl FusedBoundary {
+    pub fn has(&self, signal: Signal) -> bool {
+        self.signals.contains(&signal)
+    }
+
+    /// チェーン検出の入力（無音区間）として扱う
+    pub fn to_silence_segment(&self) -> SilenceSegment {
+        SilenceSegment {
+            start_ms: self.range.start,
+            end_ms: self.range.end,
+            duration_ms: self.range.end - self.range.start,
+        }
+    }
+}
+
+/// 境界の根拠のJSON出力
+#[derive(Debug, Clone, Serialize)]
+pub struct BoundaryOutput {
+    pub time_ms: i64,
+    pub score: f64,
+    pub signals: Vec<Signal>,
+}
+
+impl From<&FusedBoundary> for BoundaryOutput {
+    fn from(boundary: &FusedBoundary) -> Self {
+        BoundaryOutput {
+            time_ms: boundary.range.center(),
+            score: boundary.score,
+            signals: boundary.signals.clone(),
+        }
+    }
+}
+
+/// 無音区間（silencedetect）
+pub struct SilenceEvidence<'a>(pub &'a [SilenceSegment]);
+
+impl BoundaryEvidence for SilenceEvidence<'_> {
+    fn signal(&self) -> Signal {
+        Signal::Silence
+    }
+    fn weight(&self) -> f64 {
+        SILENCE_WEIGHT
+    }
+    fn ranges(&self) -> Vec<Range> {
+        self.0.iter().map(|s| s.range()).collect()
+    }
+    fn window_ms(&self) -> i64 {
+        0
+    }
+    fn standalone(&self) -> bool {
+        true
+    }
+}
+
+/// 黒画面（blackdetect）
+pub struct BlackEvidence<'a>(pub &'a [BlackSegment]);
+
+impl BoundaryEvidence for BlackEvidence<'_> {
+    fn signal(&self) -> Signal {
+        Signal::Black
+    }
+    fn weight(&self) -> f64 {
+        BLACK_WEIGHT
+    }
+    fn ranges(&self) -> Vec<Range> {
+        self.0.iter().map(|b| b.range()).collect()
+    }
+    fn window_ms(&self) -> i64 {
+        BLACK_SILENCE_WINDOW_MS
+    }
+}
+
+/// シーンチェンジ（scdet / showinfo）。snap が true なら境界をカットのフレームに合わせる
+pub struct SceneCutEvidence<'a> {
+    pub cuts: &'a [i64],
+    pub snap: bool,
+}
+
+impl BoundaryEvidence for SceneCutEvidence<'_> {
+    fn signal(&self) -> Signal {
+        Signal::SceneCut
+    }
+    fn weight(&self) -> f64 {
+        SCENE_CUT_WEIGHT
+    }
+    fn ranges(&self) -> Vec<Range> {
+        self.cuts.iter().map(|&t| Range::new(t, t)).collect()
+    }
+    fn window_ms(&self) -> i64 {
+        SCENE_CUT_WINDOW_MS
+    }
+    fn refines(&self) -> bool {
+        self.snap
+    }
+}
+
+/// ラウドネスの段差（ebur128）
+pub struct LoudnessStepEvidence {
+    steps: Vec<Range>,
+}
+
+impl LoudnessStepEvidence {
+    pub fn new(samples: &[LoudnessSample]) -> Self {
+        let steps = loudness::find_steps(samples, LOUDNESS_STEP_LU)
+            .into_iter()
+            .map(|(start, end)| Range::new(start, end))
+            .collect();
+        LoudnessStepEvidence { steps }
+    }
+}
+
+impl BoundaryEvidence for LoudnessStepEvidence {
+    fn signal(&self) -> Signal {
+        Signal::LoudnessStep
+    }
+    fn weight(&self) -> f64 {
+        LOUDNESS_STEP_WEIGHT
+    }
+    fn ranges(&self) -> Vec<Range> {
+        self.steps.clone()
+    }
+    fn window_ms(&self) -> i64 {
+        LOUDNESS_STEP_WINDOW_MS
+    }
+}
+
+/// 音声チャンネルレイアウトの変化（ashowinfo / ストリーム変化メッセージ）
+pub struct ChannelChangeEvidence<'a>(pub &'a [ChannelLayoutChange]);
+
+impl BoundaryEvidence for ChannelChangeEvidence<'_> {
+    fn signal(&self) -> Signal {
+        Signal::ChannelChange
+    }
+    fn weight(&self) -> f64 {
+        CHANNEL_CHANGE_WEIGHT
+    }
+    fn ranges(&self) -> Vec<Range> {
+        self.0.iter().map(|c| Range::new(c.time_ms, c.time_ms)).collect()
+    }
+    fn window_ms(&self) -> i64 {
+        CHANNEL_CHANGE_WINDOW_MS
+    }
+}
+
+/// 範囲 a を window_ms 広げたときに範囲 b と交差するか
+fn is_near(a: &Range, b: &Range, window_ms: i64) -> bool {
+    Range::new(a.start - window_ms, a.end + window_ms)
+        .intersect(b)
+        .is_some()
+}
+
+/// 複数の入力の境界候補を統合
+/// 1. 単独で境界になる入力（standalone）の範囲を開始時刻順に走査し、直前の境界候補の近くにあれば
+///    範囲を和集合にしてその候補を支持する。なければ新しい境界候補とする
+/// 2. その他の入力は、近くにある境界候補を支持する（refines なら範囲を絞り込む）
+pub fn fuse(sources: &[&dyn BoundaryEvidence]) -> Vec<FusedBoundary> {
+    let mut standalone: Vec<(Range, &dyn BoundaryEvidence)> = sources
+        .iter()
+        .filter(|s| s.standalone())
+        .flat_map(|&source| source.ranges().into_iter().map(move |range| (range, source)))
+        .collect();
+    standalone.sort_by_key(|(range, _)| range.start);
+
+    let mut boundaries: Vec<FusedBoundary> = Vec::new();
+    for (range, source) in standalone {
+        match boundaries.last_mut() {
+            Some(boundary) if is_near(&boundary.range, &range, source.window_ms()) => {
+                boundary.range = Range::new(boundary.range.start.min(range.start), boundary.range.end.max(range.end));
+                if !boundary.has(source.signal()) {
+                    boundary.signals.push(source.signal());
+                    boundary.score += source.weight();
+                }
+            }
+            _ => boundaries.push(FusedBoundary {
+                range,
+                score: source.weight(),
+                signals: vec![source.signal()],
+            }),
+        }
+    }
+
+    // 支持の判定は絞り込み前の範囲で行う
+    let anchors: Vec<Range> = boundaries.iter().map(|b| b.range).collect();
+    for source in sources.iter().filter(|s| !s.standalone()) {
+        // 各入力の範囲は互いに重ならないため、開始順に並べると終了も時刻順になる
+        let mut ranges = source.ranges();
+        ranges.sort_by_key(|r| r.start);
+        let window_ms = source.window_ms();
+        for (boundary, anchor) in boundaries.iter_mut().zip(&anchors) {
+            let center = anchor.center();
+            let first = ranges.partition_point(|r| r.end < anchor.start - window_ms);
+            let last = ranges.partition_point(|r| r.start <= anchor.end + window_ms);
+            let nearest = ranges[first..last.max(first)]
+                .iter()
+                .filter(|r| is_near(anchor, r, window_ms))
+                .min_by_key(|r| (r.center() - center).abs());
+            let Some(nearest) = nearest else {
+                continue;
+            };
+            boundary.signals.push(source.signal());
+            boundary.score += source.weight();
+            if source.refines() {
+                boundary.range = *nearest;
+            }
+        }
+    }
+
+    boundaries.sort_by_key(|b| b.range.start);
+    boundaries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fuse_signals() {
+        let silences = vec![
+            SilenceSegment { start_ms: 0, end_ms: 1000, duration_ms: 1000 },
+            SilenceSegment { start_ms: 14800, end_ms: 15600, duration_ms: 800 },
+        ];
+        let blacks = vec![BlackSegment { start_ms: 15700, end_ms: 15800 }];
+        let cuts = vec![700, 15715, 100000];
+        let changes = vec![
+            ChannelLayoutChange { time_ms: 15400, from: "stereo".to_string(), to: "5.1".to_string() },
+            ChannelLayoutChange { time_ms: 50000, from: "5.1".to_string(), to: "stereo".to_string() },
+        ];
+
+        let silence = SilenceEvidence(&silences);
+        let black = BlackEvidence(&blacks);
+        let scene = SceneCutEvidence { cuts: &cuts, snap: true };
+        let channel = ChannelChangeEvidence(&changes);
+        let fused = fuse(&[&silence, &black, &scene, &channel]);
+
+        assert_eq!(fused.len(), 2);
+        // 先頭: 無音 + シーンチェンジ（カットのフレームに絞り込み）
+        assert_eq!((fused[0].range.start, fused[0].range.end), (700, 700));
+        assert_eq!(fused[0].signals, vec![Signal::Silence, Signal::SceneCut]);
+        // 2番目: 全ての信号が一致
+        assert_eq!(
+            fused[1].signals,
+            vec![Signal::Silence, Signal::Black, Signal::SceneCut, Signal::ChannelChange]
+        );
+        assert!((fused[1].score - 3.0).abs() < 1e-9);
+        // 無音のないチャンネルレイアウトの変化は境界候補にならない（ブロックの分割にのみ使う）
+        assert!(fused.iter().all(|b| b.range.center() != 50000));
+
+        // snap しない場合は範囲を変えない
+        let scene = SceneCutEvidence { cuts: &cuts, snap: false };
+        let fused = fuse(&[&silence, &scene]);
+        assert_eq!((fused[0].range.start, fused[0].range.end), (0, 1000));
+        assert!(fused[0].has(Signal::SceneCut));
+    }
+
+    #[test]
+    fn test_fuse_unions_overlapping_ranges() {
+        // 再解析で追加された無音区間が既存の無音区間と重なる（ログの順は時刻順でない）
+        let silences = vec![
+            SilenceSegment { start_ms: 15000, end_ms: 15400, duration_ms: 400 },
+            SilenceSegment { start_ms: 0, end_ms: 1000, duration_ms: 1000 },
+            SilenceSegment { start_ms: 15200, end_ms: 15900, duration_ms: 700 },
+        ];
+        let fused = fuse(&[&SilenceEvidence(&silences)]);
+        let ranges: Vec<(i64, i64)> = fused.iter().map(|b| (b.range.start, b.range.end)).collect();
+        assert_eq!(ranges, vec![(0, 1000), (15000, 15900)]);
+        assert_eq!(fused[1].signals, vec![Signal::Silence]);
+        assert!((fused[1].score - SILENCE_WEIGHT).abs() < 1e-9);
+    }
+}
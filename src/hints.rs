@@ -0,0 +1,84 @@
+// 番組表（EPG）による検出のヒント
+//
+// 録画機が知っている予定開始・終了時刻（ファイル先頭からの位置）、ジャンル、放送局を読み込み、
+// 本編の開始・終了位置の推定、想定されるCM枠の数、放送局のプロファイルの選択に使う。
+
+use std::fs;
+
+use serde::Deserialize;
+
+/// ヒントファイルの内容
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Hints {
+    /// 予定開始時刻のファイル先頭からの位置
+    #[serde(default)]
+    pub scheduled_start_ms: Option<i64>,
+    /// 予定終了時刻のファイル先頭からの位置
+    #[serde(default)]
+    pub scheduled_end_ms: Option<i64>,
+    #[serde(default)]
+    pub genre: Option<String>,
+    /// プロファイルの選択に使う放送局ID（--profiles DIR の DIR/<channel_id>.json）
+    #[serde(default)]
+    pub channel_id: Option<String>,
+    /// 想定されるCM枠の数（省略時はジャンルと番組の長さから推定）
+    #[serde(default)]
+    pub expected_breaks: Option<usize>,
+}
+
+impl Hints {
+    /// 想定されるCM枠の数
+    pub fn expected_breaks(&self) -> Option<usize> {
+        if self.expected_breaks.is_some() {
+            return self.expected_breaks;
+        }
+        let duration_ms = self.scheduled_end_ms? - self.scheduled_start_ms.unwrap_or(0);
+        let interval_ms = break_interval_ms(self.genre.as_deref()?)?;
+        Some((duration_ms as f64 / interval_ms as f64).round().max(0.0) as usize)
+    }
+}
+
+/// ジャンルごとのCM枠の間隔の目安
+/// 30分のアニメはOP後・Aパート後・ED後の3回、1時間のドラマは4〜5回程度
+fn break_interval_ms(genre: &str) -> Option<i64> {
+    let minutes = match genre {
+        "anime" => 10,
+        "variety" => 8,
+        "drama" => 12,
+        "news" | "documentary" | "sports" | "movie" => 15,
+        _ => return None,
+    };
+    Some(minutes * 60_000)
+}
+
+/// ヒントファイルを読み込む
+pub fn read_hints(path: &str) -> Result<Hints, String> {
+    let content = fs::read_to_string(path).map_err(|e| format!("Failed to read hints {}: {}", path, e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Invalid hints {}: {}", path, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expected_breaks() {
+        let hints: Hints = serde_json::from_str(
+            r#"{"scheduled_start_ms": 30000, "scheduled_end_ms": 1830000, "genre": "anime", "channel_id": "BS11"}"#,
+        )
+        .unwrap();
+        assert_eq!(hints.expected_breaks(), Some(3));
+
+        let drama = Hints {
+            scheduled_end_ms: Some(3_600_000),
+            genre: Some("drama".to_string()),
+            ..Hints::default()
+        };
+        assert_eq!(drama.expected_breaks(), Some(5));
+
+        let unknown = Hints { genre: Some("shopping".to_string()), ..hints.clone() };
+        assert_eq!(unknown.expected_breaks(), None);
+        let explicit = Hints { expected_breaks: Some(2), ..hints };
+        assert_eq!(explicit.expected_breaks(), Some(2));
+    }
+}
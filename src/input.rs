@@ -0,0 +1,235 @@
+// 入力形式（ffmpegのログ / 検出結果のJSON / CSV / WAV / raw PCM）の読み込み
+
+use std::io::Read;
+
+use serde::Deserialize;
+
+use crate::audio::{self, AudioOptions};
+use crate::parser::{ParsedLog, parse_ffmpeg_log};
+use crate::timestamp::Timestamp;
+use crate::{BlackSegment, ChannelLayoutChange, SilenceSegment};
+
+/// 標準入力の形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum InputFormat {
+    /// ffmpeg silencedetect 等の stderr 出力
+    #[default]
+    Ffmpeg,
+    /// cm-detector 自身のJSON出力（silence_segments 等を再利用）
+    Json,
+    /// 無音区間の start,end（秒）のCSV
+    Csv,
+    /// WAVファイル（内蔵の無音検出を使用）
+    Wav,
+    /// ヘッダのない PCM（ffmpeg -f s16le 等の出力、内蔵の無音検出を使用）
+    Pcm,
+}
+
+impl InputFormat {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "ffmpeg" => Some(InputFormat::Ffmpeg),
+            "json" => Some(InputFormat::Json),
+            "csv" => Some(InputFormat::Csv),
+            "wav" => Some(InputFormat::Wav),
+            "pcm" => Some(InputFormat::Pcm),
+            _ => None,
+        }
+    }
+}
+
+/// 指定された形式で入力を読み込む
+/// WAV / PCM は全体をメモリに読み込まずに無音検出する
+pub fn read_input(format: InputFormat, reader: &mut impl Read, options: &AudioOptions) -> Result<ParsedLog, String> {
+    match format {
+        InputFormat::Ffmpeg => Ok(parse_ffmpeg_log(&read_text(reader)?)),
+        InputFormat::Json => parse_json_input(&read_text(reader)?),
+        InputFormat::Csv => parse_csv_input(&read_text(reader)?),
+        InputFormat::Wav => {
+            let (pcm, length) = audio::read_wav_header(reader)?;
+            let analysis = match length {
+                Some(length) => audio::detect_silence(&mut reader.take(length), pcm, options.silence)?,
+                None => audio::detect_silence(reader, pcm, options.silence)?,
+            };
+            Ok(audio_parsed_log(analysis))
+        }
+        InputFormat::Pcm => Ok(audio_parsed_log(audio::detect_silence(
+            reader,
+            options.pcm,
+            options.silence,
+        )?)),
+    }
+}
+
+/// テキスト入力を読み込む（不正なUTF-8は置換する）
+fn read_text(reader: &mut impl Read) -> Result<String, String> {
+    let mut raw = Vec::new();
+    reader
+        .read_to_end(&mut raw)
+        .map_err(|e| format!("Failed to read input: {}", e))?;
+    Ok(String::from_utf8_lossy(&raw).into_owned())
+}
+
+fn audio_parsed_log(analysis: audio::AudioAnalysis) -> ParsedLog {
+    ParsedLog {
+        silence_segments: analysis.silence_segments,
+        duration_ms: Some(analysis.duration_ms),
+        silence_threshold: analysis.threshold,
+        ..ParsedLog::default()
+    }
+}
+
+/// JSON入力の区間
+#[derive(Debug, Deserialize)]
+struct SegmentJson {
+    start_ms: i64,
+    end_ms: i64,
+}
+
+/// JSON入力（cm-detector の出力のうち再検出に必要な部分）
+#[derive(Debug, Deserialize)]
+struct DetectionJson {
+    #[serde(default)]
+    recorded_at: Option<String>,
+    #[serde(default)]
+    duration_ms: Option<i64>,
+    silence_segments: Vec<SegmentJson>,
+    #[serde(default)]
+    black_segments: Vec<SegmentJson>,
+    #[serde(default)]
+    scene_cuts_ms: Vec<i64>,
+    #[serde(default)]
+    channel_layout_changes: Vec<ChannelLayoutChange>,
+}
+
+/// cm-detector のJSON出力から無音区間等を読み込む
+fn parse_json_input(data: &str) -> Result<ParsedLog, String> {
+    let json: DetectionJson =
+        serde_json::from_str(data).map_err(|e| format!("Invalid JSON input: {}", e))?;
+    let recorded_at = match &json.recorded_at {
+        Some(value) => Some(
+            Timestamp::parse_rfc3339(value)
+                .ok_or_else(|| format!("Invalid RFC3339 timestamp in recorded_at: {}", value))?,
+        ),
+        None => None,
+    };
+
+    Ok(ParsedLog {
+        silence_segments: json
+            .silence_segments
+            .iter()
+            .map(|s| SilenceSegment {
+                start_ms: s.start_ms,
+                end_ms: s.end_ms,
+                duration_ms: s.end_ms - s.start_ms,
+            })
+            .collect(),
+        black_segments: json
+            .black_segments
+            .iter()
+            .map(|b| BlackSegment {
+                start_ms: b.start_ms,
+                end_ms: b.end_ms,
+            })
+            .collect(),
+        scene_cuts: json.scene_cuts_ms,
+        channel_layout_changes: json.channel_layout_changes,
+        duration_ms: json.duration_ms,
+        recorded_at,
+        ..ParsedLog::default()
+    })
+}
+
+/// 無音区間のCSVを読み込む
+/// 1行に1区間の "start,end"（秒）。ヘッダ行は省略可能で、"start_ms,end_ms" の場合はミリ秒として扱う
+fn parse_csv_input(data: &str) -> Result<ParsedLog, String> {
+    let mut parsed = ParsedLog::default();
+    let mut scale = 1000.0;
+    for (i, line) in data.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut columns = line.split(',').map(str::trim);
+        let (Some(start), Some(end)) = (columns.next(), columns.next()) else {
+            return Err(format!("Invalid CSV line {}: {}", i + 1, line));
+        };
+
+        let (Ok(start), Ok(end)) = (start.parse::<f64>(), end.parse::<f64>()) else {
+            // 先頭のヘッダ行
+            if parsed.silence_segments.is_empty() && start.chars().any(|c| c.is_ascii_alphabetic()) {
+                scale = if start.ends_with("_ms") { 1.0 } else { 1000.0 };
+                continue;
+            }
+            return Err(format!("Invalid CSV line {}: {}", i + 1, line));
+        };
+        if end < start {
+            return Err(format!("Invalid CSV line {}: end is before start", i + 1));
+        }
+
+        let start_ms = (start * scale).round() as i64;
+        let end_ms = (end * scale).round() as i64;
+        parsed.silence_segments.push(SilenceSegment {
+            start_ms,
+            end_ms,
+            duration_ms: end_ms - start_ms,
+        });
+    }
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(format: InputFormat, data: &str) -> Result<ParsedLog, String> {
+        read_input(format, &mut data.as_bytes(), &AudioOptions::default())
+    }
+
+    fn bounds(parsed: &ParsedLog) -> Vec<(i64, i64)> {
+        parsed.silence_segments.iter().map(|s| (s.start_ms, s.end_ms)).collect()
+    }
+
+    #[test]
+    fn test_parse_json_input() {
+        let json = r#"{
+            "input_file": "stdin",
+            "recorded_at": "2024-04-01T21:00:00.000+09:00",
+            "duration_ms": 1800000,
+            "start_offset_ms": 3000,
+            "cm_blocks": [],
+            "silence_segments": [
+                {"start_ms": 100000, "end_ms": 100500, "duration_ms": 500},
+                {"start_ms": 115000, "end_ms": 115400, "duration_ms": 400}
+            ],
+            "black_segments": [{"start_ms": 100100, "end_ms": 100200}],
+            "channel_layout_changes": [{"time_ms": 100200, "from": "5.1", "to": "stereo"}]
+        }"#;
+        let parsed = read(InputFormat::Json, json).unwrap();
+        assert_eq!(bounds(&parsed), vec![(100000, 100500), (115000, 115400)]);
+        assert_eq!(parsed.black_segments.len(), 1);
+        assert_eq!(parsed.channel_layout_changes[0].to, "stereo");
+        assert_eq!(parsed.duration_ms, Some(1800000));
+        assert_eq!(parsed.recorded_at.unwrap().local_seconds_of_day(), 21 * 3600);
+
+        assert!(read(InputFormat::Json, "{}").is_err());
+        assert!(read(InputFormat::Json, "silence_start: 1.0").is_err());
+    }
+
+    #[test]
+    fn test_parse_csv_input() {
+        let csv = "start,end\n100.0,100.5\n\n# comment\n115,115.4\n";
+        let parsed = read(InputFormat::Csv, csv).unwrap();
+        assert_eq!(bounds(&parsed), vec![(100000, 100500), (115000, 115400)]);
+
+        // ミリ秒のヘッダ、ヘッダなし
+        let parsed = read(InputFormat::Csv, "start_ms,end_ms\n100000,100500\n").unwrap();
+        assert_eq!(bounds(&parsed), vec![(100000, 100500)]);
+        let parsed = read(InputFormat::Csv, "1.5,2.0").unwrap();
+        assert_eq!(bounds(&parsed), vec![(1500, 2000)]);
+
+        assert!(read(InputFormat::Csv, "1.0,abc").is_err());
+        assert!(read(InputFormat::Csv, "2.0,1.0").is_err());
+        assert!(read(InputFormat::Csv, "1.0").is_err());
+    }
+}
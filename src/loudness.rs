@@ -0,0 +1,162 @@
+// ebur128 ラウドネスによるCM/本編の判定
+
+use serde::Serialize;
+
+const GATE_LUFS: f64 = -70.0; // これ未満のサンプルは無音として除外（BS.1770 の絶対ゲート）
+const MIN_SUMMARY_SAMPLES: usize = 10; // 判定に必要な最小サンプル数
+const STEP_WINDOW_MS: i64 = 2000; // 段差の判定に使う前後の区間の長さ
+const STEP_GUARD_MS: i64 = 500; // 段差の判定で境界の前後に空ける長さ（無音区間を含めない）
+const MIN_STEP_SAMPLES: usize = 3; // 段差の判定に必要な前後それぞれのサンプル数
+
+/// ebur128 の1サンプル
+#[derive(Debug, Clone)]
+pub struct LoudnessSample {
+    pub time_ms: i64,
+    pub momentary_lufs: f64,
+    pub short_term_lufs: f64,
+}
+
+/// 区間内のラウドネスの要約
+#[derive(Debug, Clone, Serialize)]
+pub struct LoudnessSummary {
+    pub mean_momentary_lufs: f64,
+    pub mean_short_term_lufs: f64,
+    /// momentary の10〜90パーセンタイルの幅（LU）。CMはコンプレッサーで狭く、本編は広い
+    pub range_lu: f64,
+    pub samples: usize,
+}
+
+/// [start_ms, end_ms) 内のサンプルを要約。ゲートを通過したサンプルがなければ None
+/// samples は時刻順
+pub fn summarize(samples: &[LoudnessSample], start_ms: i64, end_ms: i64) -> Option<LoudnessSummary> {
+    let first = samples.partition_point(|s| s.time_ms < start_ms);
+    let gated: Vec<&LoudnessSample> = samples[first..]
+        .iter()
+        .take_while(|s| s.time_ms < end_ms)
+        .filter(|s| s.momentary_lufs >= GATE_LUFS)
+        .collect();
+    if gated.is_empty() {
+        return None;
+    }
+
+    let count = gated.len() as f64;
+    let mut momentary: Vec<f64> = gated.iter().map(|s| s.momentary_lufs).collect();
+    momentary.sort_by(f64::total_cmp);
+
+    Some(LoudnessSummary {
+        mean_momentary_lufs: momentary.iter().sum::<f64>() / count,
+        mean_short_term_lufs: gated.iter().map(|s| s.short_term_lufs).sum::<f64>() / count,
+        range_lu: percentile(&momentary, 0.9) - percentile(&momentary, 0.1),
+        samples: gated.len(),
+    })
+}
+
+/// 区間のラウドネスが本編らしいか（ダイナミクスが max_range_lu より広い）
+/// サンプルが少ない場合は判定しない
+pub fn looks_like_program(summary: &LoudnessSummary, max_range_lu: f64) -> bool {
+    summary.samples >= MIN_SUMMARY_SAMPLES && summary.range_lu > max_range_lu
+}
+
+/// ラウドネスが min_step_lu 以上変化する時刻の範囲（[start_ms, end_ms]）
+/// 各サンプルの前後 STEP_WINDOW_MS の momentary の平均を比較し、連続する時刻をまとめる
+/// samples は時刻順
+pub fn find_steps(samples: &[LoudnessSample], min_step_lu: f64) -> Vec<(i64, i64)> {
+    let gated: Vec<&LoudnessSample> = samples.iter().filter(|s| s.momentary_lufs >= GATE_LUFS).collect();
+    let mut prefix = vec![0.0];
+    for s in &gated {
+        prefix.push(prefix.last().unwrap() + s.momentary_lufs);
+    }
+    // [start_ms, end_ms) 内の平均（サンプル不足なら None）
+    let mean = |start_ms: i64, end_ms: i64| {
+        let first = gated.partition_point(|s| s.time_ms < start_ms);
+        let last = gated.partition_point(|s| s.time_ms < end_ms);
+        (last >= first + MIN_STEP_SAMPLES).then(|| (prefix[last] - prefix[first]) / (last - first) as f64)
+    };
+
+    let mut steps: Vec<(i64, i64)> = Vec::new();
+    for s in samples {
+        let t = s.time_ms;
+        let before = mean(t - STEP_WINDOW_MS, t - STEP_GUARD_MS);
+        let after = mean(t + STEP_GUARD_MS, t + STEP_WINDOW_MS);
+        let (Some(before), Some(after)) = (before, after) else {
+            continue;
+        };
+        if (after - before).abs() < min_step_lu {
+            continue;
+        }
+        match steps.last_mut() {
+            Some(last) if t - last.1 <= STEP_GUARD_MS => last.1 = t,
+            _ => steps.push((t, t)),
+        }
+    }
+    steps
+}
+
+/// ソート済みの値のパーセンタイル（最近傍）
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    let idx = ((sorted.len() - 1) as f64 * p).round() as usize;
+    sorted[idx]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn samples(values: &[f64]) -> Vec<LoudnessSample> {
+        values
+            .iter()
+            .enumerate()
+            .map(|(i, &lufs)| LoudnessSample {
+                time_ms: i as i64 * 100,
+                momentary_lufs: lufs,
+                short_term_lufs: lufs,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_summarize() {
+        // 無音（-120）はゲートで除外される
+        let samples = samples(&[-120.0, -24.0, -23.0, -25.0, -24.0, -120.0]);
+        let summary = summarize(&samples, 0, 600).unwrap();
+        assert_eq!(summary.samples, 4);
+        assert!((summary.mean_momentary_lufs + 24.0).abs() < 1e-9);
+        assert!((summary.range_lu - 2.0).abs() < 1e-9);
+
+        // 範囲外・全て無音
+        assert!(summarize(&samples, 1000, 2000).is_none());
+        assert!(summarize(&samples, 0, 100).is_none());
+    }
+
+    #[test]
+    fn test_looks_like_program() {
+        // CM: -24 ± 1 LU
+        let cm: Vec<f64> = (0..20).map(|i| -24.0 + (i % 3) as f64 - 1.0).collect();
+        let summary = summarize(&samples(&cm), 0, 2000).unwrap();
+        assert!(!looks_like_program(&summary, 15.0));
+
+        // 本編: 台詞と静かな場面で -45〜-20
+        let program: Vec<f64> = (0..20).map(|i| if i % 2 == 0 { -45.0 } else { -20.0 }).collect();
+        let summary = summarize(&samples(&program), 0, 2000).unwrap();
+        assert!(looks_like_program(&summary, 15.0));
+
+        // サンプル不足では判定しない
+        let summary = summarize(&samples(&program[..4]), 0, 2000).unwrap();
+        assert!(!looks_like_program(&summary, 15.0));
+    }
+
+    #[test]
+    fn test_find_steps() {
+        // 0-5s: -35 LUFS の本編、5.0-5.5s: 無音、5.5-10s: -23 LUFS のCM
+        let mut values = vec![-35.0; 50];
+        values.extend([-120.0; 5]);
+        values.extend([-23.0; 45]);
+        let steps = find_steps(&samples(&values), 6.0);
+        assert_eq!(steps.len(), 1);
+        let (start, end) = steps[0];
+        assert!(start <= 5000 && end >= 5500, "{:?}", steps[0]);
+
+        // 一定のラウドネスでは段差なし
+        assert!(find_steps(&samples(&[-24.0; 100]), 6.0).is_empty());
+    }
+}
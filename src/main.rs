@@ -1,5 +1,39 @@
-use serde::Serialize;
-use std::io::{self, Read};
+mod audio;
+mod epoch;
+mod eval;
+mod evidence;
+mod hints;
+mod input;
+mod loudness;
+mod parser;
+mod profile;
+mod rescan;
+mod structure;
+mod threshold;
+mod timestamp;
+mod train;
+mod validate;
+
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::io;
+use audio::{AudioOptions, SampleFormat};
+use epoch::Epoch;
+use evidence::{
+    BlackEvidence, BoundaryEvidence, BoundaryOutput, ChannelChangeEvidence, FusedBoundary,
+    LoudnessStepEvidence, SceneCutEvidence, Signal, SilenceEvidence,
+};
+use loudness::{LoudnessSample, LoudnessSummary};
+use input::InputFormat;
+use parser::{ChannelCombine, ParsedLog};
+use rescan::{RescanMode, RescanWindow};
+use structure::{BreakFit, StructureModel, StructureReport};
+use std::process;
+use threshold::{ThresholdMethod, ThresholdReport};
+use timestamp::Timestamp;
+use validate::{Anomaly, ValidationMode};
 
 // 定数
 const TOLERANCE_MS: i64 = 500;
@@ -11,6 +45,26 @@ const MIN_STANDARD_UNITS: usize = 2; // ブロックに必要な標準単位の
 const MAX_STANDARD_UNITS: i64 = 5; // 標準単位の上限（75秒 = 5 x 15秒）
 const STANDARD_UNIT_SEC: f64 = 15.0; // 標準CM単位（秒）
 const SHORT_UNITS: [f64; 2] = [5.0, 10.0]; // 短時間CM単位（秒）
+const SPONSOR_MIN_SEC: f64 = 5.0; // 提供クレジットの最小長（秒）
+const SPONSOR_MAX_SEC: f64 = 10.0; // 提供クレジットの最大長（秒）
+const BRIDGE_WINDOW_SEC: i64 = 180; // ブリッジ区間を許可する :00/:30 前後の範囲（秒）
+const JST_OFFSET_SEC: i64 = 9 * 3600; // creation_time（UTC）から変換する際のオフセット
+const BLACK_SILENCE_WINDOW_MS: i64 = 500; // 無音区間と黒画面を同じ境界とみなす距離
+const SCENE_CUT_WINDOW_MS: i64 = 200; // 無音区間とシーンチェンジを同じ境界とみなす距離
+const MAX_CM_LOUDNESS_RANGE_LU: f64 = 15.0; // CMブロック内のラウドネス幅の上限（超えると本編とみなす）
+const CHANNEL_CHANGE_WINDOW_MS: i64 = 1000; // ブロック境界とチャンネルレイアウトの変化を同じ境界とみなす距離
+const SCHEDULE_TOLERANCE_MS: i64 = 5000; // 予定開始・終了時刻と本編の開始・終了位置のずれの許容範囲
+const END_OFFSET_WINDOW_MS: i64 = 30000; // 録画の終端からこの範囲内で終わるCMブロックを末尾のCMとみなす
+const PADDING_WEIGHT: f64 = 0.5; // 録画の余白にあるブロック（前後の番組のCM）を番組のCMとして数える重み
+
+/// verbose の場合のみ検出の経過を標準エラー出力に表示する
+macro_rules! progress {
+    ($verbose:expr, $($arg:tt)*) => {
+        if $verbose {
+            eprintln!($($arg)*);
+        }
+    };
+}
 
 // 無音区間を表す構造体（範囲として扱う）
 #[derive(Debug, Clone)]
@@ -20,6 +74,27 @@ struct SilenceSegment {
     duration_ms: i64,
 }
 
+// 黒画面区間を表す構造体（ffmpeg blackdetect）
+#[derive(Debug, Clone)]
+struct BlackSegment {
+    start_ms: i64,
+    end_ms: i64,
+}
+
+impl BlackSegment {
+    fn range(&self) -> Range {
+        Range::new(self.start_ms, self.end_ms)
+    }
+}
+
+// 音声チャンネルレイアウトの変化（例: 5.1 → stereo）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ChannelLayoutChange {
+    time_ms: i64,
+    from: String,
+    to: String,
+}
+
 // 範囲を表す構造体（境界点の候補範囲）
 #[derive(Debug, Clone, Copy)]
 struct Range {
@@ -32,6 +107,10 @@ impl Range {
         Range { start, end }
     }
 
+    fn center(&self) -> i64 {
+        (self.start + self.end) / 2
+    }
+
     /// 2つの範囲の交差を計算。交差がなければNone
     fn intersect(&self, other: &Range) -> Option<Range> {
         let start = self.start.max(other.start);
@@ -49,6 +128,239 @@ impl Range {
     }
 }
 
+impl SilenceSegment {
+    fn range(&self) -> Range {
+        Range::new(self.start_ms, self.end_ms)
+    }
+
+    fn center_ms(&self) -> i64 {
+        (self.start_ms + self.end_ms) / 2
+    }
+}
+
+/// 隣接する無音区間の間隔の計測方法
+/// - Center: 中心点間
+/// - Edge: 前区間の終端から次区間の始端まで（非無音部分の長さ）
+/// - RangeIntersection: 無音区間を範囲として扱い、期待範囲との交差で判定
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum GapMetric {
+    Center,
+    Edge,
+    RangeIntersection,
+}
+
+impl GapMetric {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "center" => Some(GapMetric::Center),
+            "edge" => Some(GapMetric::Edge),
+            "range" => Some(GapMetric::RangeIntersection),
+            _ => None,
+        }
+    }
+
+    /// 代表値としての間隔（ミリ秒）
+    /// RangeIntersection は範囲の中心点間を代表値とする
+    fn gap_ms(self, prev: &Range, curr: &Range) -> i64 {
+        match self {
+            GapMetric::Center | GapMetric::RangeIntersection => curr.center() - prev.center(),
+            GapMetric::Edge => curr.start - prev.end,
+        }
+    }
+
+    /// 間隔をセグメントとして出力する際の (start_ms, end_ms)
+    fn segment_bounds(self, prev: &Range, curr: &Range) -> (i64, i64) {
+        match self {
+            GapMetric::Center | GapMetric::RangeIntersection => (prev.center(), curr.center()),
+            GapMetric::Edge => (prev.end, curr.start),
+        }
+    }
+
+    /// 間隔が expected_ms ± tolerance_ms に一致するか判定
+    /// 一致すれば、次の判定に引き継ぐ境界点の候補範囲を返す
+    /// - Center: 中心点間で判定し、prev をオフセットした期待範囲と curr の交差を引き継ぐ（従来の短時間単位の判定）
+    /// - Edge: 端点間で判定し、curr 全体を引き継ぐ
+    /// - RangeIntersection: prev をオフセットした期待範囲と curr の交差で判定し、交差を引き継ぐ
+    fn accepts(self, prev: &Range, curr: &Range, expected_ms: i64, tolerance_ms: i64) -> Option<Range> {
+        let target = Range::new(
+            prev.offset(expected_ms - tolerance_ms).start,
+            prev.offset(expected_ms + tolerance_ms).end,
+        );
+        match self {
+            GapMetric::Center | GapMetric::Edge => {
+                if (self.gap_ms(prev, curr) - expected_ms).abs() > tolerance_ms {
+                    None
+                } else if self == GapMetric::Center {
+                    // 中心点が期待範囲内にあるので交差は空にならない
+                    curr.intersect(&target)
+                } else {
+                    Some(*curr)
+                }
+            }
+            GapMetric::RangeIntersection => curr.intersect(&target),
+        }
+    }
+}
+
+/// 各パスで使用するギャップ計測方法
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct GapMetrics {
+    /// チェーン検出（標準単位）
+    chain_standard: GapMetric,
+    /// チェーン検出（短時間単位）
+    chain_short: GapMetric,
+    /// チェーン内セグメントの境界
+    chain_segment: GapMetric,
+    /// ブロック間統合
+    merge: GapMetric,
+    /// 境界拡張
+    extension: GapMetric,
+    /// 拡張セグメントの境界
+    extension_segment: GapMetric,
+}
+
+impl GapMetrics {
+    /// 従来の挙動（互換プリセット）
+    /// チェーン検出は中心点で単位数を決めて範囲交差で判定、短時間単位は中心点間、
+    /// セグメント長は end-to-start、境界拡張は edge-to-edge で判定し中心点で出力
+    /// ブロック間統合はギャップ内のチェーンを範囲交差でたどる（チェーン検出と同じ判定）
+    fn legacy() -> Self {
+        GapMetrics {
+            chain_standard: GapMetric::RangeIntersection,
+            chain_short: GapMetric::Center,
+            chain_segment: GapMetric::Edge,
+            merge: GapMetric::RangeIntersection,
+            extension: GapMetric::Edge,
+            extension_segment: GapMetric::Center,
+        }
+    }
+
+    /// 全パスで同じ計測方法を使用
+    fn uniform(metric: GapMetric) -> Self {
+        GapMetrics {
+            chain_standard: metric,
+            chain_short: metric,
+            chain_segment: metric,
+            merge: metric,
+            extension: metric,
+            extension_segment: metric,
+        }
+    }
+
+    fn parse(name: &str) -> Option<Self> {
+        if name == "legacy" {
+            Some(GapMetrics::legacy())
+        } else {
+            GapMetric::parse(name).map(GapMetrics::uniform)
+        }
+    }
+}
+
+/// 黒画面（blackdetect）の使い方
+/// - Off: 使用しない
+/// - Boost: 無音区間と黒画面の一致率をブロックの confidence として出力
+/// - Require: 黒画面を伴わない無音区間を除外してから検出（confidence も出力）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BlackMode {
+    Off,
+    Boost,
+    Require,
+}
+
+impl BlackMode {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "off" => Some(BlackMode::Off),
+            "boost" => Some(BlackMode::Boost),
+            "require" => Some(BlackMode::Require),
+            _ => None,
+        }
+    }
+}
+
+// 検出パラメータ
+#[derive(Debug, Clone)]
+struct DetectorConfig {
+    gap_metrics: GapMetrics,
+    sponsor_policy: SponsorPolicy,
+    /// ブロック外側に隣接する提供クレジットらしい区間を、sponsor_policy が cm のときブロックに含める
+    /// （デフォルトでは sponsor_segments に出力するのみで、ブロックの範囲は変えない）
+    absorb_adjacent_sponsors: bool,
+    /// ブロック端の非標準セグメントを長さだけで提供クレジットとする
+    /// （デフォルトでは黒画面・シーンチェンジ・ラウドネスの段差が境界を支持する場合のみ）
+    sponsor_edge_by_duration: bool,
+    bridges: Vec<BridgeType>,
+    bridge_window_sec: i64,
+    /// 録画開始の壁時計時刻（:00/:30 付近の判定に使用）
+    recording_start: Option<Timestamp>,
+    black_mode: BlackMode,
+    /// 境界をシーンチェンジのフレームに合わせる（scdet 等の出力があり、--scene-cuts snap を指定した場合）
+    snap_to_scene_cuts: bool,
+    /// CMブロック内のラウドネス幅の上限（ebur128 の出力がある場合）。None で判定しない
+    max_cm_loudness_range_lu: Option<f64>,
+    /// チャンネルレイアウトの変化をまたぐブロックを分割する（ashowinfo / ストリーム変化メッセージがある場合）
+    split_at_channel_changes: bool,
+    /// PTSの不連続で時刻をエポックに分割し、エポックごとに検出する
+    split_pts_epochs: bool,
+    /// 本編開始位置（最初の無音区間・先頭のCMブロック）の探索範囲
+    start_offset_window: Range,
+    /// 番組の予定開始時刻（EPG）。録画開始時刻と合わせて本編開始位置の推定に使用
+    scheduled_start: Option<Timestamp>,
+    /// 番組の予定終了時刻（EPG）。録画開始時刻と合わせて本編終了位置の推定に使用
+    scheduled_end: Option<Timestamp>,
+    /// 番組の長さ（予定終了時刻がない場合に本編開始位置からの終了位置の推定に使用）
+    program_duration_ms: Option<i64>,
+    /// 録画の終端からこの範囲内で終わるCMブロックを末尾のCMとみなす
+    end_offset_window_ms: i64,
+    /// 予定開始・終了時刻のファイル先頭からの位置（ヒントファイル）。scheduled_start / scheduled_end より優先
+    scheduled_start_ms: Option<i64>,
+    scheduled_end_ms: Option<i64>,
+    /// 想定されるCM枠の数（--breaks / ヒントファイル）
+    expected_breaks: Option<usize>,
+    /// 想定されるCM枠の位置（番組の長さに対する割合 0.0〜1.0）
+    break_positions: Vec<f64>,
+    /// 単位の判定の許容誤差（ミリ秒）
+    tolerance_ms: i64,
+    /// 短時間CM単位（秒）
+    short_units: Vec<f64>,
+    /// 最終フィルタ: ブロックに必要な標準単位の最小数
+    min_standard_units: usize,
+    /// 最終フィルタ: ブロックの最小長（秒）
+    min_block_duration_sec: f64,
+}
+
+impl Default for DetectorConfig {
+    fn default() -> Self {
+        DetectorConfig {
+            gap_metrics: GapMetrics::legacy(),
+            sponsor_policy: SponsorPolicy::Cm,
+            absorb_adjacent_sponsors: false,
+            sponsor_edge_by_duration: false,
+            bridges: Vec::new(),
+            bridge_window_sec: BRIDGE_WINDOW_SEC,
+            recording_start: None,
+            black_mode: BlackMode::Boost,
+            snap_to_scene_cuts: false,
+            max_cm_loudness_range_lu: Some(MAX_CM_LOUDNESS_RANGE_LU),
+            split_at_channel_changes: true,
+            split_pts_epochs: true,
+            start_offset_window: Range::new(START_OFFSET_MIN_MS, START_OFFSET_MAX_MS),
+            scheduled_start: None,
+            scheduled_end: None,
+            program_duration_ms: None,
+            end_offset_window_ms: END_OFFSET_WINDOW_MS,
+            scheduled_start_ms: None,
+            scheduled_end_ms: None,
+            expected_breaks: None,
+            break_positions: Vec::new(),
+            tolerance_ms: TOLERANCE_MS,
+            short_units: SHORT_UNITS.to_vec(),
+            min_standard_units: MIN_STANDARD_UNITS,
+            min_block_duration_sec: MIN_BLOCK_DURATION_SEC,
+        }
+    }
+}
+
 // CM候補区間を表す構造体
 #[derive(Debug, Clone, Serialize)]
 struct CmCandidate {
@@ -56,6 +368,88 @@ struct CmCandidate {
     end_ms: i64,
     duration_sec: f64,
     is_standard: bool, // 標準単位パスでマッチしたか（短時間単位ではない）
+    kind: SegmentKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bridge_type: Option<String>, // kind == Bridge の場合のブリッジ種別名
+    #[serde(skip_serializing_if = "Option::is_none")]
+    loudness: Option<LoudnessSummary>, // ebur128 の出力がある場合のラウドネス要約
+}
+
+impl CmCandidate {
+    fn new(start_ms: i64, end_ms: i64, is_standard: bool, kind: SegmentKind) -> Self {
+        CmCandidate {
+            start_ms,
+            end_ms,
+            duration_sec: (end_ms - start_ms) as f64 / 1000.0,
+            is_standard,
+            kind,
+            bridge_type: None,
+            loudness: None,
+        }
+    }
+
+    fn bridge(start_ms: i64, end_ms: i64, bridge: &BridgeType) -> Self {
+        CmCandidate {
+            bridge_type: Some(bridge.name.clone()),
+            ..CmCandidate::new(start_ms, end_ms, false, SegmentKind::Bridge)
+        }
+    }
+}
+
+/// セグメントの種別
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum SegmentKind {
+    Cm,
+    /// 提供クレジット（「この番組は…の提供でお送りします」）
+    Sponsor,
+    /// ブリッジ区間（ステーションID・ネットワークブレイク・時報など）
+    Bridge,
+}
+
+/// ブリッジ区間の種別
+/// 15秒単位に乗らない長さ（20s/40sなど）でもCMブロック内・端に現れることを許可する
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct BridgeType {
+    name: String,
+    duration_ms: i64,
+}
+
+impl BridgeType {
+    /// "station_id=20" 形式をパース
+    fn parse(spec: &str) -> Option<Self> {
+        let (name, duration) = spec.split_once('=')?;
+        let duration_sec: f64 = duration.parse().ok()?;
+        if name.is_empty() || duration_sec <= 0.0 {
+            return None;
+        }
+        Some(BridgeType {
+            name: name.to_string(),
+            duration_ms: (duration_sec * 1000.0).round() as i64,
+        })
+    }
+}
+
+/// 提供クレジットの扱い
+/// - Program: 本編側に残す（CMブロックから除外）
+/// - Cm: CMと一緒にカットする（CMブロックに含める）
+/// - Separate: CMブロックから除外し、sponsor_segments として別に出力
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SponsorPolicy {
+    Program,
+    Cm,
+    Separate,
+}
+
+impl SponsorPolicy {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "program" => Some(SponsorPolicy::Program),
+            "cm" => Some(SponsorPolicy::Cm),
+            "separate" => Some(SponsorPolicy::Separate),
+            _ => None,
+        }
+    }
 }
 
 // CMブロックを表す構造体
@@ -65,15 +459,109 @@ struct CmBlock {
     end_ms: i64,
     duration_sec: f64,
     segments: Vec<CmCandidate>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    start_at: Option<String>, // 壁時計での開始時刻（録画開始時刻が分かる場合）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    end_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    confidence: Option<f64>, // 境界の無音区間のうち黒画面を伴うものの割合
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    boundaries: Vec<BoundaryOutput>, // ブロック内の境界とそれを支持する信号
+    #[serde(skip_serializing_if = "Option::is_none")]
+    padding: Option<Padding>, // 予定開始前・予定終了後の余白にあるブロック（ヒントファイルがある場合）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    padding_weight: Option<f64>, // 余白にあるブロックを番組のCMとして数える重み（padding がある場合）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    structure: Option<BreakFit>, // 番組構成のモデルとの対応（--breaks / --break-positions の場合）
+}
+
+/// 録画の余白の位置
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Padding {
+    BeforeStart,
+    AfterEnd,
+}
+
+impl CmBlock {
+    fn new(start_ms: i64, end_ms: i64, segments: Vec<CmCandidate>) -> Self {
+        CmBlock {
+            start_ms,
+            end_ms,
+            duration_sec: (end_ms - start_ms) as f64 / 1000.0,
+            segments,
+            start_at: None,
+            end_at: None,
+            confidence: None,
+            boundaries: Vec::new(),
+            padding: None,
+            padding_weight: None,
+            structure: None,
+        }
+    }
+}
+
+// 本編区間（CMブロック以外の部分）を表す構造体
+#[derive(Debug, Clone, Serialize)]
+struct ProgramSegment {
+    start_ms: i64,
+    end_ms: i64,
+    duration_sec: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    start_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    end_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    channel_layout: Option<String>, // 区間の音声チャンネルレイアウト（変化が検出された場合）
 }
 
 // JSON出力用の構造体
 #[derive(Debug, Serialize)]
 struct OutputJson {
     input_file: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    recorded_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duration_ms: Option<i64>, // 入力の長さ（--input-format json で再検出する際に使用）
     start_offset_ms: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    start_offset_reason: Option<StartOffsetReason>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    end_offset_ms: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    end_offset_reason: Option<EndOffsetReason>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    expected_breaks: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    structure: Option<StructureReport>,
     cm_blocks: Vec<CmBlock>,
+    program_segments: Vec<ProgramSegment>,
+    sponsor_segments: Vec<CmCandidate>,
     silence_segments: Vec<SilenceSegmentOutput>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    black_segments: Vec<BlackSegmentOutput>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    scene_cuts_ms: Vec<i64>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    channel_layout_changes: Vec<ChannelLayoutChange>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    log_anomalies: Vec<Anomaly>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    recovered_lines: Option<usize>, // 非ASCII文字や進捗表示との連結から無音区間のキーを取り出した行数
+    #[serde(skip_serializing_if = "Option::is_none")]
+    unparseable_lines: Option<usize>, // 無音区間のキーを含むが値を読み取れなかった行数
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    timeline_epochs: Vec<Epoch>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    silence_threshold: Option<ThresholdReport>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    rescan_windows: Vec<RescanWindow>,
+}
+
+#[derive(Debug, Serialize)]
+struct BlackSegmentOutput {
+    start_ms: i64,
+    end_ms: i64,
 }
 
 #[derive(Debug, Serialize)]
@@ -83,64 +571,467 @@ struct SilenceSegmentOutput {
     duration_ms: i64,
 }
 
-fn main() {
-    // 無音区間を検出（標準入力からffmpeg silencedetectの出力を読み取る）
-    eprintln!("Reading silence detection data from stdin...");
-    let mut raw_input = Vec::new();
-    io::stdin()
-        .read_to_end(&mut raw_input)
-        .expect("Failed to read from stdin");
-    let stdin_data = String::from_utf8_lossy(&raw_input);
-    let silence_segments = parse_silence_output(&stdin_data);
-    let start_offset_ms = detect_start_offset_ms(&silence_segments);
-
-    eprintln!("Found {} silence segments", silence_segments.len());
-
-    // CMブロックを検出（新アルゴリズム: 範囲ベース境界 + 短時間単位もチェーン継続）
-    let mut blocks = detect_blocks_range_based(&silence_segments);
-    eprintln!("Detected {} CM blocks (before merge)", blocks.len());
+// コマンドライン引数
+#[derive(Debug, Default)]
+struct CliArgs {
+    config: DetectorConfig,
+    /// 録画開始時刻を記載したサイドカーファイル
+    recorded_at_file: Option<String>,
+    /// ログの creation_time を録画開始時刻として検出にも使う
+    recorded_at_from_log: bool,
+    input_format: InputFormat,
+    /// チャンネルごとの無音区間（silencedetect=mono=1）の統合方法
+    channel_combine: ChannelCombine,
+    /// 無音区間の異常（順序・重なり・重複・長さの不一致）の扱い
+    validation: ValidationMode,
+    /// 標準入力の代わりに読み込むファイル
+    input_path: Option<String>,
+    /// WAV / PCM 入力の内蔵無音検出の設定
+    audio: AudioOptions,
+    /// 境界の取りこぼしが疑われる範囲の再解析
+    rescan: RescanMode,
+    /// --rescan ffmpeg で再解析する動画
+    rescan_source: Option<String>,
+    /// 番組表（EPG）のヒントファイル
+    hints_path: Option<String>,
+    /// 放送局のプロファイル（直接指定）
+    profile_path: Option<String>,
+    /// 放送局のプロファイルのディレクトリ（ヒントファイルの channel_id で選択）
+    profiles_dir: Option<String>,
+    /// ログの作成に使った silencedetect の n（--silence-noise auto での推奨値の計算に使用、省略時は -40dB）
+    log_noise_db: Option<f64>,
+    /// コマンドライン引数（プロファイルの適用後に再度適用する）
+    args: Vec<String>,
+}
 
-    // 短時間単位による隣接ブロック統合（後処理）
-    blocks = merge_blocks_with_short_units(&blocks, &silence_segments);
-    eprintln!("After between-block merge: {} CM blocks", blocks.len());
+/// "2-30" 形式の秒数の範囲をパース
+fn parse_seconds_range(value: &str) -> Option<Range> {
+    let (min, max) = value.split_once('-')?;
+    let min: f64 = min.parse().ok()?;
+    let max: f64 = max.parse().ok()?;
+    if min < 0.0 || max < min {
+        return None;
+    }
+    Some(Range::new((min * 1000.0).round() as i64, (max * 1000.0).round() as i64))
+}
 
-    // CMブロック境界の短時間単位を拡張（後処理）
-    blocks = extend_block_boundaries_with_short_units(&blocks, &silence_segments);
-    eprintln!("After boundary extension: {} CM blocks", blocks.len());
+/// "5,10" 形式の短時間単位（秒）をパース（"none" で短時間単位を使わない）
+fn parse_short_units(value: &str) -> Option<Vec<f64>> {
+    if value == "none" {
+        return Some(Vec::new());
+    }
+    value
+        .split(',')
+        .map(|v| v.trim().parse().ok().filter(|u: &f64| u.is_finite() && *u > 0.0))
+        .collect()
+}
 
-    // Debug: print pre-filter block statistics
-    eprintln!("\n=== Pre-filter block analysis ===");
-    eprintln!("{:<5} {:>12} {:>8} {:>10} {:>10}", "Block", "Duration(s)", "StdUnits", "Dur>=60?", "Units>=2?");
-    for (i, block) in blocks.iter().enumerate() {
-        let std_units = count_standard_units(block);
-        let dur_ok = block.duration_sec >= MIN_BLOCK_DURATION_SEC;
-        let units_ok = std_units >= MIN_STANDARD_UNITS;
-        eprintln!("{:<5} {:>12.1} {:>8} {:>10} {:>10}",
-            i + 1,
-            block.duration_sec,
-            std_units,
-            if dur_ok { "YES" } else { "NO" },
-            if units_ok { "YES" } else { "NO" }
-        );
-        // Show segment details for blocks that pass duration but fail units
-        if dur_ok && !units_ok {
-            eprintln!("  Block {} segments:", i + 1);
-            for (j, seg) in block.segments.iter().enumerate() {
-                eprintln!("    Seg {}: {:>7.2}s  is_standard={}", j + 1, seg.duration_sec, seg.is_standard);
+/// "-40dB" / "-40" 形式の閾値をパース
+fn parse_noise_db(value: &str) -> Result<f64, String> {
+    value
+        .trim_end_matches("dB")
+        .parse()
+        .map_err(|_| format!("Invalid noise threshold: {}", value))
+}
+
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<CliArgs, String> {
+    let args: Vec<String> = args.into_iter().collect();
+    let mut cli = parse_args_from(CliArgs::default(), args.iter().cloned())?;
+    cli.args = args;
+    Ok(cli)
+}
+
+/// cli（プロファイルを反映した設定など）にコマンドライン引数を上書きする
+fn parse_args_from(mut cli: CliArgs, args: impl IntoIterator<Item = String>) -> Result<CliArgs, String> {
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--gap-metric" => {
+                let value = args.next().ok_or("--gap-metric requires a value")?;
+                cli.config.gap_metrics = GapMetrics::parse(&value).ok_or_else(|| {
+                    format!("Unknown gap metric: {} (expected legacy, center, edge or range)", value)
+                })?;
+            }
+            "--sponsor-policy" => {
+                let value = args.next().ok_or("--sponsor-policy requires a value")?;
+                cli.config.sponsor_policy = SponsorPolicy::parse(&value).ok_or_else(|| {
+                    format!("Unknown sponsor policy: {} (expected program, cm or separate)", value)
+                })?;
+            }
+            "--sponsor-adjacent" => {
+                let value = args.next().ok_or("--sponsor-adjacent requires a value")?;
+                cli.config.absorb_adjacent_sponsors = match value.as_str() {
+                    "absorb" => true,
+                    "report" => false,
+                    _ => return Err(format!("Unknown sponsor adjacent mode: {} (expected report or absorb)", value)),
+                };
+            }
+            "--sponsor-edge" => {
+                let value = args.next().ok_or("--sponsor-edge requires a value")?;
+                cli.config.sponsor_edge_by_duration = match value.as_str() {
+                    "duration" => true,
+                    "evidence" => false,
+                    _ => return Err(format!("Unknown sponsor edge mode: {} (expected evidence or duration)", value)),
+                };
+            }
+            "--bridge" => {
+                let value = args.next().ok_or("--bridge requires a value")?;
+                let bridge = BridgeType::parse(&value).ok_or_else(|| {
+                    format!("Invalid bridge: {} (expected NAME=SECONDS)", value)
+                })?;
+                cli.config.bridges.push(bridge);
+            }
+            "--bridge-window" => {
+                let value = args.next().ok_or("--bridge-window requires a value")?;
+                cli.config.bridge_window_sec = value
+                    .parse()
+                    .ok()
+                    .filter(|sec| *sec >= 0)
+                    .ok_or_else(|| format!("Invalid bridge window: {}", value))?;
+            }
+            "--recorded-at" => {
+                let value = args.next().ok_or("--recorded-at requires a value")?;
+                cli.config.recording_start = Some(
+                    Timestamp::parse_rfc3339(&value)
+                        .ok_or_else(|| format!("Invalid RFC3339 timestamp: {}", value))?,
+                );
+            }
+            "--recorded-at-file" => {
+                let value = args.next().ok_or("--recorded-at-file requires a value")?;
+                cli.recorded_at_file = Some(value);
+            }
+            "--recorded-at-from-log" => {
+                cli.recorded_at_from_log = true;
+            }
+            "--black-mode" => {
+                let value = args.next().ok_or("--black-mode requires a value")?;
+                cli.config.black_mode = BlackMode::parse(&value).ok_or_else(|| {
+                    format!("Unknown black mode: {} (expected off, boost or require)", value)
+                })?;
+            }
+            "--scene-cuts" => {
+                let value = args.next().ok_or("--scene-cuts requires a value")?;
+                cli.config.snap_to_scene_cuts = match value.as_str() {
+                    "snap" => true,
+                    "off" => false,
+                    _ => return Err(format!("Unknown scene cut mode: {} (expected snap or off)", value)),
+                };
+            }
+            "--max-cm-loudness-range" => {
+                let value = args.next().ok_or("--max-cm-loudness-range requires a value")?;
+                cli.config.max_cm_loudness_range_lu = if value == "off" {
+                    None
+                } else {
+                    Some(value.parse().map_err(|_| format!("Invalid loudness range: {}", value))?)
+                };
+            }
+            "--channel-changes" => {
+                let value = args.next().ok_or("--channel-changes requires a value")?;
+                cli.config.split_at_channel_changes = match value.as_str() {
+                    "split" => true,
+                    "off" => false,
+                    _ => return Err(format!("Unknown channel change mode: {} (expected split or off)", value)),
+                };
+            }
+            "--input-format" => {
+                let value = args.next().ok_or("--input-format requires a value")?;
+                cli.input_format = InputFormat::parse(&value).ok_or_else(|| {
+                    format!("Unknown input format: {} (expected ffmpeg, json or csv)", value)
+                })?;
+            }
+            "--channels" => {
+                let value = args.next().ok_or("--channels requires a value")?;
+                cli.channel_combine = ChannelCombine::parse(&value).ok_or_else(|| {
+                    format!("Unknown channel policy: {} (expected all, any or a channel number)", value)
+                })?;
+            }
+            "--validate" => {
+                let value = args.next().ok_or("--validate requires a value")?;
+                cli.validation = ValidationMode::parse(&value).ok_or_else(|| {
+                    format!("Unknown validation mode: {} (expected warn, repair or reject)", value)
+                })?;
+            }
+            "--pts-epochs" => {
+                let value = args.next().ok_or("--pts-epochs requires a value")?;
+                cli.config.split_pts_epochs = match value.as_str() {
+                    "split" => true,
+                    "off" => false,
+                    _ => return Err(format!("Unknown PTS epoch mode: {} (expected split or off)", value)),
+                };
+            }
+            "--input" => {
+                let value = args.next().ok_or("--input requires a value")?;
+                cli.input_path = Some(value);
+            }
+            "--silence-noise" => {
+                let value = args.next().ok_or("--silence-noise requires a value")?;
+                if value == "auto" {
+                    cli.audio.silence.adaptive = true;
+                } else {
+                    cli.audio.silence.noise_db = parse_noise_db(&value)?;
+                }
+            }
+            "--log-noise" => {
+                let value = args.next().ok_or("--log-noise requires a value")?;
+                cli.log_noise_db = Some(parse_noise_db(&value)?);
+            }
+            "--silence-duration" => {
+                let value = args.next().ok_or("--silence-duration requires a value")?;
+                cli.audio.silence.min_duration_sec = value
+                    .parse()
+                    .map_err(|_| format!("Invalid silence duration: {}", value))?;
+            }
+            "--pcm-rate" => {
+                let value = args.next().ok_or("--pcm-rate requires a value")?;
+                cli.audio.pcm.sample_rate = value
+                    .parse()
+                    .map_err(|_| format!("Invalid sample rate: {}", value))?;
+            }
+            "--pcm-channels" => {
+                let value = args.next().ok_or("--pcm-channels requires a value")?;
+                cli.audio.pcm.channels = value
+                    .parse()
+                    .map_err(|_| format!("Invalid channel count: {}", value))?;
             }
+            "--pcm-format" => {
+                let value = args.next().ok_or("--pcm-format requires a value")?;
+                cli.audio.pcm.sample_format = SampleFormat::parse(&value).ok_or_else(|| {
+                    format!("Unknown PCM format: {} (expected s16le, s24le, s32le or f32le)", value)
+                })?;
+            }
+            "--rescan" => {
+                let value = args.next().ok_or("--rescan requires a value")?;
+                cli.rescan = RescanMode::parse(&value).ok_or_else(|| {
+                    format!("Unknown rescan mode: {} (expected off, native or ffmpeg)", value)
+                })?;
+            }
+            "--rescan-source" => {
+                let value = args.next().ok_or("--rescan-source requires a value")?;
+                cli.rescan_source = Some(value);
+            }
+            "--scheduled-end" => {
+                let value = args.next().ok_or("--scheduled-end requires a value")?;
+                cli.config.scheduled_end = Some(
+                    Timestamp::parse_rfc3339(&value)
+                        .ok_or_else(|| format!("Invalid RFC3339 timestamp: {}", value))?,
+                );
+            }
+            "--program-duration" => {
+                let value = args.next().ok_or("--program-duration requires a value")?;
+                let seconds: f64 = value
+                    .parse()
+                    .ok()
+                    .filter(|s: &f64| s.is_finite() && *s >= 0.0)
+                    .ok_or_else(|| format!("Invalid program duration: {}", value))?;
+                cli.config.program_duration_ms = Some((seconds * 1000.0).round() as i64);
+            }
+            "--end-window" => {
+                let value = args.next().ok_or("--end-window requires a value")?;
+                let seconds: f64 = value
+                    .parse()
+                    .ok()
+                    .filter(|s: &f64| s.is_finite() && *s >= 0.0)
+                    .ok_or_else(|| format!("Invalid end window: {}", value))?;
+                cli.config.end_offset_window_ms = (seconds * 1000.0).round() as i64;
+            }
+            "--hints" => {
+                let value = args.next().ok_or("--hints requires a value")?;
+                cli.hints_path = Some(value);
+            }
+            "--profile" => {
+                let value = args.next().ok_or("--profile requires a value")?;
+                cli.profile_path = Some(value);
+            }
+            "--profiles" => {
+                let value = args.next().ok_or("--profiles requires a value")?;
+                cli.profiles_dir = Some(value);
+            }
+            "--breaks" => {
+                let value = args.next().ok_or("--breaks requires a value")?;
+                cli.config.expected_breaks =
+                    Some(value.parse().map_err(|_| format!("Invalid break count: {}", value))?);
+            }
+            "--break-positions" => {
+                let value = args.next().ok_or("--break-positions requires a value")?;
+                cli.config.break_positions = structure::parse_positions(&value).ok_or_else(|| {
+                    format!("Invalid break positions: {} (expected percentages such as 10,50,95)", value)
+                })?;
+            }
+            "--tolerance" => {
+                let value = args.next().ok_or("--tolerance requires a value")?;
+                cli.config.tolerance_ms = value
+                    .parse()
+                    .ok()
+                    .filter(|ms| *ms >= 0)
+                    .ok_or_else(|| format!("Invalid tolerance: {}", value))?;
+            }
+            "--short-units" => {
+                let value = args.next().ok_or("--short-units requires a value")?;
+                cli.config.short_units = parse_short_units(&value).ok_or_else(|| {
+                    format!("Invalid short units: {} (expected seconds such as 5,10 or none)", value)
+                })?;
+            }
+            "--min-standard-units" => {
+                let value = args.next().ok_or("--min-standard-units requires a value")?;
+                cli.config.min_standard_units = value
+                    .parse()
+                    .map_err(|_| format!("Invalid standard unit count: {}", value))?;
+            }
+            "--min-block-duration" => {
+                let value = args.next().ok_or("--min-block-duration requires a value")?;
+                cli.config.min_block_duration_sec = value
+                    .parse()
+                    .ok()
+                    .filter(|s: &f64| s.is_finite() && *s >= 0.0)
+                    .ok_or_else(|| format!("Invalid block duration: {}", value))?;
+            }
+            "--start-window" => {
+                let value = args.next().ok_or("--start-window requires a value")?;
+                cli.config.start_offset_window = parse_seconds_range(&value)
+                    .ok_or_else(|| format!("Invalid start window: {} (expected MIN-MAX in seconds)", value))?;
+            }
+            "--scheduled-start" => {
+                let value = args.next().ok_or("--scheduled-start requires a value")?;
+                cli.config.scheduled_start = Some(
+                    Timestamp::parse_rfc3339(&value)
+                        .ok_or_else(|| format!("Invalid RFC3339 timestamp: {}", value))?,
+                );
+            }
+            _ => return Err(format!("Unknown argument: {}", arg)),
         }
     }
-    eprintln!("=================================\n");
+    match cli.rescan {
+        RescanMode::Native
+            if cli.input_path.is_none() || !matches!(cli.input_format, InputFormat::Wav | InputFormat::Pcm) =>
+        {
+            return Err("--rescan native requires --input with --input-format wav or pcm".to_string());
+        }
+        RescanMode::Ffmpeg if cli.rescan_source.is_none() => {
+            return Err("--rescan ffmpeg requires --rescan-source".to_string());
+        }
+        _ => {}
+    }
+    Ok(cli)
+}
 
-    // 最終フィルタ: 標準単位数と最小時間のチェック（マージ後に実施）
-    blocks = filter_blocks_by_standard_units(blocks);
-    eprintln!("Final {} CM blocks (after standard unit filter)", blocks.len());
+fn main() {
+    // サブコマンド（正解付きの履歴からのプロファイルの学習、正解との比較）
+    let subcommand = match env::args().nth(1).as_deref() {
+        Some("train") => Some(train::run(env::args().skip(2))),
+        Some("eval") => Some(eval::run(env::args().skip(2))),
+        _ => None,
+    };
+    if let Some(result) = subcommand {
+        if let Err(e) = result {
+            eprintln!("{}", e);
+            process::exit(2);
+        }
+        return;
+    }
+
+    let mut cli = match parse_args(env::args().skip(1)).and_then(apply_hints_and_profile) {
+        Ok(cli) => cli,
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(2);
+        }
+    };
+
+    // 無音区間を検出（ffmpeg silencedetectの出力、検出結果のJSON/CSV、または WAV/PCM を読み取る）
+    let result = match &cli.input_path {
+        Some(path) => {
+            eprintln!("Reading silence detection data from {}...", path);
+            fs::File::open(path)
+                .map_err(|e| format!("Failed to open {}: {}", path, e))
+                .and_then(|file| input::read_input(cli.input_format, &mut io::BufReader::new(file), &cli.audio))
+        }
+        None => {
+            eprintln!("Reading silence detection data from stdin...");
+            input::read_input(cli.input_format, &mut io::stdin().lock(), &cli.audio)
+        }
+    };
+    let parsed = match result {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(2);
+        }
+    };
+
+    // 録画開始時刻: --recorded-at > サイドカーファイル > ログの creation_time（JSON入力では recorded_at）
+    // ログの値は --recorded-at-from-log を指定した場合のみ検出に使い、それ以外は出力に記録するだけ
+    if cli.config.recording_start.is_none() {
+        cli.config.recording_start = match &cli.recorded_at_file {
+            Some(path) => match read_recorded_at_file(path) {
+                Ok(start) => Some(start),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    process::exit(2);
+                }
+            },
+            None if cli.recorded_at_from_log => parsed.recorded_at,
+            None => None,
+        };
+    }
+    let recorded_at = cli.config.recording_start.or(parsed.recorded_at);
+    if let Some(start) = cli.config.recording_start {
+        eprintln!("Recording started at {}", start.to_rfc3339());
+    }
+    let duration_ms = parsed.duration_ms;
+    // 行の回復・読み取り失敗の件数（再解析や train では繰り返し表示しないよう、ここでのみ表示する）
+    let recovered_lines = (parsed.recovered_lines > 0).then_some(parsed.recovered_lines);
+    let unparseable_lines = (parsed.unparseable_lines > 0).then_some(parsed.unparseable_lines);
+    if let Some(count) = recovered_lines {
+        eprintln!("Recovered silence keys from {} mixed or non-ASCII lines", count);
+    }
+    if let Some(count) = unparseable_lines {
+        eprintln!("Could not parse {} lines containing silence keys", count);
+    }
+
+    let Detection {
+        silence_segments,
+        black_segments,
+        scene_cuts,
+        channel_layout_changes,
+        log_anomalies,
+        epochs,
+        silence_threshold,
+        rescan_windows,
+        structure,
+        mut blocks,
+        sponsor_segments,
+        start_offset,
+        end_offset,
+        mut program_segments,
+    } = match detect_from_parsed(parsed, &cli, true) {
+        Ok(detection) => detection,
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(2);
+        }
+    };
+    let start_offset_ms = start_offset.map(|start| start.offset_ms);
+    let end_offset_ms = end_offset.map(|end| end.offset_ms);
+
+    // 壁時計時刻を付与
+    if let Some(start) = cli.config.recording_start {
+        annotate_wall_clock(&mut blocks, &mut program_segments, start);
+    }
 
     // JSON出力
     let output = OutputJson {
-        input_file: "stdin".to_string(),
+        input_file: cli.input_path.clone().unwrap_or_else(|| "stdin".to_string()),
+        recorded_at: recorded_at.map(|start| start.to_rfc3339()),
+        duration_ms,
         start_offset_ms,
+        start_offset_reason: start_offset.map(|start| start.reason),
+        end_offset_ms,
+        end_offset_reason: end_offset.map(|end| end.reason),
+        expected_breaks: cli.config.expected_breaks,
+        structure,
         cm_blocks: blocks,
+        program_segments,
+        sponsor_segments,
         silence_segments: silence_segments
             .iter()
             .map(|s| SilenceSegmentOutput {
@@ -149,73 +1040,651 @@ fn main() {
                 duration_ms: s.duration_ms,
             })
             .collect(),
+        black_segments: black_segments
+            .iter()
+            .map(|b| BlackSegmentOutput {
+                start_ms: b.start_ms,
+                end_ms: b.end_ms,
+            })
+            .collect(),
+        scene_cuts_ms: scene_cuts,
+        channel_layout_changes,
+        log_anomalies,
+        recovered_lines,
+        unparseable_lines,
+        timeline_epochs: epochs,
+        silence_threshold,
+        rescan_windows,
     };
 
     let json = serde_json::to_string_pretty(&output).expect("Failed to serialize JSON");
     println!("{}", json);
 }
 
-/// Check if a string contains only ASCII characters
-fn is_ascii_line(line: &str) -> bool {
-    line.bytes().all(|b| b.is_ascii())
+/// 入力からの検出結果
+struct Detection {
+    /// 入力の無音区間（再解析で見つかったものを含む）
+    silence_segments: Vec<SilenceSegment>,
+    black_segments: Vec<BlackSegment>,
+    scene_cuts: Vec<i64>,
+    channel_layout_changes: Vec<ChannelLayoutChange>,
+    log_anomalies: Vec<Anomaly>,
+    epochs: Vec<Epoch>,
+    silence_threshold: Option<ThresholdReport>,
+    rescan_windows: Vec<RescanWindow>,
+    structure: Option<StructureReport>,
+    blocks: Vec<CmBlock>,
+    sponsor_segments: Vec<CmCandidate>,
+    start_offset: Option<StartOffset>,
+    end_offset: Option<EndOffset>,
+    program_segments: Vec<ProgramSegment>,
 }
 
-// FFmpeg silencedetect出力から無音区間をパース
-fn parse_silence_output(output: &str) -> Vec<SilenceSegment> {
-    let mut segments = Vec::new();
-    let mut current_start: Option<f64> = None;
-    let mut skipped_lines = 0;
+/// 読み込んだ入力から、前処理・チェーン検出・最終フィルタ・本編区間の計算までを行う（main と train で共通）
+/// verbose の場合は経過を標準エラー出力に表示する
+fn detect_from_parsed(mut parsed: ParsedLog, cli: &CliArgs, verbose: bool) -> Result<Detection, String> {
+    let config = &cli.config;
+
+    // チャンネルごとの無音区間（mono=1）を統合
+    if !parsed.channel_silences.is_empty() {
+        progress!(
+            verbose,
+            "Combining per-channel silences of {} channels ({:?})",
+            parsed.channel_silences.len(),
+            cli.channel_combine
+        );
+        parsed.silence_segments = parser::combine_channel_silences(&parsed.channel_silences, cli.channel_combine);
+    }
 
-    for line in output.lines() {
-        // Skip lines containing non-ASCII characters to avoid parsing issues
-        if !is_ascii_line(line) {
-            skipped_lines += 1;
-            continue;
+    // 無音判定の閾値: WAV / PCM では推定した値を、ログ等では無音区間の密度からの推奨値を報告
+    let silence_threshold = if !cli.audio.silence.adaptive {
+        None
+    } else if let Some(report) = parsed.silence_threshold.take() {
+        progress!(
+            verbose,
+            "Estimated silence threshold: {:.0}dB (noise floor {:.0}dB)",
+            report.noise_db,
+            report.noise_floor_db.unwrap_or(report.noise_db)
+        );
+        Some(report)
+    } else {
+        let log_noise_db = cli.log_noise_db.unwrap_or(AudioOptions::default().silence.noise_db);
+        let report =
+            threshold::recommend_noise_threshold(&parsed.silence_segments, parsed.duration_ms, log_noise_db);
+        progress!(
+            verbose,
+            "{:.2} silences per minute with n={}dB: recommended silencedetect n={}dB",
+            report.silences_per_minute.unwrap_or(0.0),
+            log_noise_db,
+            report.noise_db
+        );
+        Some(report)
+    };
+
+    // PTSの不連続で時刻をエポックに分割し、ファイル先頭からの時刻に補正
+    let mut epochs = if config.split_pts_epochs {
+        epoch::split_epochs(&parsed.silence_segments, parsed.duration_ms)
+    } else {
+        Vec::new()
+    };
+    if epochs.len() > 1 {
+        progress!(verbose, "Found {} PTS discontinuities", epochs.len() - 1);
+        for epoch in &epochs[1..] {
+            progress!(
+                verbose,
+                "  {:?} at {}ms (offset {}ms)",
+                epoch.discontinuity.unwrap(),
+                epoch.start_ms,
+                epoch.offset_ms
+            );
         }
+        parsed.silence_segments = epoch::apply_offsets(&parsed.silence_segments, &epochs);
+        // 無音区間以外の信号はエポックへの対応付けができないため使用しない
+        if !parsed.black_segments.is_empty()
+            || !parsed.scene_cuts.is_empty()
+            || !parsed.loudness_samples.is_empty()
+            || !parsed.channel_layout_changes.is_empty()
+        {
+            progress!(verbose, "Ignoring black/scene/loudness/channel signals across PTS discontinuities");
+            parsed.black_segments.clear();
+            parsed.scene_cuts.clear();
+            parsed.loudness_samples.clear();
+            parsed.channel_layout_changes.clear();
+        }
+    } else {
+        epochs.clear();
+    }
 
-        if line.contains("silence_start:") {
-            if let Some(start) = extract_timestamp(line, "silence_start:") {
-                current_start = Some(start);
+    // ログの異常をチェック
+    let mut log_anomalies = std::mem::take(&mut parsed.anomalies);
+    log_anomalies.extend(validate::check_silences(&parsed.silence_segments));
+    if !log_anomalies.is_empty() {
+        progress!(verbose, "Found {} log anomalies", log_anomalies.len());
+        for anomaly in &log_anomalies {
+            progress!(verbose, "  {:?} at {}ms: {}", anomaly.kind, anomaly.time_ms, anomaly.detail);
+        }
+        match cli.validation {
+            ValidationMode::Warn => {}
+            ValidationMode::Repair => {
+                parsed.silence_segments = validate::repair_silences(&parsed.silence_segments);
+                progress!(verbose, "Repaired silence segments: {}", parsed.silence_segments.len());
             }
-        } else if line.contains("silence_end:") {
-            if let (Some(start), Some(end)) = (current_start, extract_timestamp(line, "silence_end:")) {
-                segments.push(SilenceSegment {
-                    start_ms: (start * 1000.0) as i64,
-                    end_ms: (end * 1000.0) as i64,
-                    duration_ms: ((end - start) * 1000.0) as i64,
-                });
-                current_start = None;
+            ValidationMode::Reject => return Err("Rejected input with log anomalies".to_string()),
+        }
+    }
+
+    let mut all_silence_segments = parsed.silence_segments;
+    let black_segments = parsed.black_segments;
+    let scene_cuts = parsed.scene_cuts;
+    let mut loudness_samples = parsed.loudness_samples;
+    loudness_samples.sort_by_key(|s| s.time_ms);
+    let channel_layout_changes = parsed.channel_layout_changes;
+    let use_black = config.black_mode != BlackMode::Off && !black_segments.is_empty();
+
+    progress!(verbose, "Found {} silence segments", all_silence_segments.len());
+    if !black_segments.is_empty() {
+        progress!(verbose, "Found {} black segments", black_segments.len());
+    }
+    if !scene_cuts.is_empty() {
+        progress!(verbose, "Found {} scene cuts", scene_cuts.len());
+    }
+    if !loudness_samples.is_empty() {
+        progress!(verbose, "Found {} loudness samples", loudness_samples.len());
+    }
+    if !channel_layout_changes.is_empty() {
+        progress!(verbose, "Found {} channel layout changes", channel_layout_changes.len());
+    }
+
+    // 各信号の境界候補を統合し、チェーン検出の入力とする
+    let mut boundaries = fuse_boundaries(
+        &all_silence_segments,
+        &black_segments,
+        &scene_cuts,
+        &loudness_samples,
+        &channel_layout_changes,
+        config,
+    );
+    for signal in [Signal::Black, Signal::SceneCut, Signal::LoudnessStep, Signal::ChannelChange] {
+        let count = boundaries.iter().filter(|b| b.has(signal)).count();
+        if count > 0 {
+            progress!(verbose, "{} boundaries supported by {:?}", count, signal);
+        }
+    }
+    let mut silence_segments: Vec<SilenceSegment> =
+        boundaries.iter().map(FusedBoundary::to_silence_segment).collect();
+
+    let mut blocks = detect_epoch_blocks(&silence_segments, &epochs, config, verbose);
+
+    // 境界の取りこぼしが疑われる範囲を感度を上げて再解析し、チェーン検出をやり直す
+    let mut rescan_windows = Vec::new();
+    if cli.rescan != RescanMode::Off {
+        if epochs.is_empty() {
+            rescan_windows = rescan::suspect_windows(&silence_segments, &blocks);
+        } else {
+            progress!(verbose, "Skipping rescan across PTS discontinuities");
+        }
+    }
+    if !rescan_windows.is_empty() {
+        progress!(verbose, "Rescanning {} suspected windows", rescan_windows.len());
+        let result = match (cli.rescan, &cli.input_path, &cli.rescan_source) {
+            (RescanMode::Native, Some(path), _) => {
+                let noise_db = match &silence_threshold {
+                    Some(report) if report.method == ThresholdMethod::Adaptive => report.noise_db,
+                    _ => cli.audio.silence.noise_db,
+                };
+                rescan::rescan_pcm_file(
+                    path,
+                    cli.input_format == InputFormat::Wav,
+                    cli.audio.pcm,
+                    &mut rescan_windows,
+                    rescan::relaxed_params(noise_db),
+                )
+            }
+            (RescanMode::Ffmpeg, _, Some(source)) => {
+                let noise_db = cli.log_noise_db.unwrap_or(AudioOptions::default().silence.noise_db);
+                rescan::rescan_with_ffmpeg(source, &mut rescan_windows, rescan::relaxed_params(noise_db))
+            }
+            _ => Ok(Vec::new()),
+        };
+        match result {
+            Ok(found) => {
+                let added = rescan::merge_silences(&mut all_silence_segments, found);
+                progress!(verbose, "Found {} additional silence segments", added);
+                if added > 0 {
+                    boundaries = fuse_boundaries(
+                        &all_silence_segments,
+                        &black_segments,
+                        &scene_cuts,
+                        &loudness_samples,
+                        &channel_layout_changes,
+                        config,
+                    );
+                    silence_segments = boundaries.iter().map(FusedBoundary::to_silence_segment).collect();
+                    blocks = detect_epoch_blocks(&silence_segments, &epochs, config, verbose);
+                }
+            }
+            Err(e) => {
+                progress!(verbose, "Rescan failed: {}", e);
+                rescan_windows.clear();
             }
         }
     }
 
-    if skipped_lines > 0 {
-        eprintln!("Skipped {} lines containing non-ASCII characters", skipped_lines);
+    // チャンネルレイアウトの変化をまたぐブロックを分割（後処理）
+    if config.split_at_channel_changes && !channel_layout_changes.is_empty() {
+        blocks = split_blocks_at_channel_changes(blocks, &channel_layout_changes, verbose);
+        progress!(verbose, "After channel layout split: {} CM blocks", blocks.len());
     }
 
-    segments
+    // Debug: print pre-filter block statistics
+    if verbose {
+        eprintln!("\n=== Pre-filter block analysis ===");
+        eprintln!(
+            "{:<5} {:>12} {:>8} {:>10} {:>10}",
+            "Block",
+            "Duration(s)",
+            "StdUnits",
+            format!("Dur>={}?", config.min_block_duration_sec),
+            format!("Units>={}?", config.min_standard_units)
+        );
+        for (i, block) in blocks.iter().enumerate() {
+            let std_units = count_standard_units(block);
+            let dur_ok = block.duration_sec >= config.min_block_duration_sec;
+            let units_ok = std_units >= config.min_standard_units;
+            eprintln!("{:<5} {:>12.1} {:>8} {:>10} {:>10}",
+                i + 1,
+                block.duration_sec,
+                std_units,
+                if dur_ok { "YES" } else { "NO" },
+                if units_ok { "YES" } else { "NO" }
+            );
+            // Show segment details for blocks that pass duration but fail units
+            if dur_ok && !units_ok {
+                eprintln!("  Block {} segments:", i + 1);
+                for (j, seg) in block.segments.iter().enumerate() {
+                    eprintln!("    Seg {}: {:>7.2}s  is_standard={}", j + 1, seg.duration_sec, seg.is_standard);
+                }
+            }
+        }
+        eprintln!("=================================\n");
+    }
+
+    // 最終フィルタ: 標準単位数と最小時間のチェック（マージ後に実施）
+    let mut weak_blocks: Vec<CmBlock> = blocks.iter().filter(|b| !passes_standard_units(b, config)).cloned().collect();
+    blocks = filter_blocks_by_standard_units(blocks, config);
+    progress!(verbose, "Final {} CM blocks (after standard unit filter)", blocks.len());
+
+    // ラウドネスが本編らしいブロックを除外（ebur128 の出力がある場合のみ）
+    if !loudness_samples.is_empty() {
+        blocks = filter_blocks_by_loudness(blocks, &loudness_samples, config, verbose);
+        weak_blocks = filter_blocks_by_loudness(weak_blocks, &loudness_samples, config, verbose);
+        progress!(verbose, "Final {} CM blocks (after loudness filter)", blocks.len());
+    }
+
+    // 番組構成のモデル: 想定位置の弱いブロックを救済し、想定外のブロックに印を付ける
+    let model = StructureModel {
+        break_count: config.expected_breaks,
+        break_positions: config.break_positions.clone(),
+    };
+    let structure = if model.is_empty() {
+        None
+    } else {
+        let runtime = Range::new(
+            scheduled_start_offset_ms(config).unwrap_or(0),
+            scheduled_end_offset_ms(config)
+                .or(parsed.duration_ms)
+                .or_else(|| all_silence_segments.iter().map(|s| s.end_ms).max())
+                .unwrap_or(0),
+        );
+        let report = structure::apply_structure(&mut blocks, weak_blocks, &model, runtime);
+        progress!(
+            verbose,
+            "Structure fit {:.3}: {} rescued, {} unexpected of {} expected breaks",
+            report.fit_score, report.rescued_breaks, report.unexpected_breaks, report.expected_breaks
+        );
+        Some(report)
+    };
+
+    // 提供クレジットの分類（ポリシーに応じてブロックから除外）
+    let (mut blocks, mut sponsor_segments) =
+        classify_sponsor_segments(blocks, &boundaries, config);
+    progress!(verbose, "Found {} sponsor segments", sponsor_segments.len());
+
+    // セグメントごとのラウドネス要約
+    if !loudness_samples.is_empty() {
+        let segments = blocks
+            .iter_mut()
+            .flat_map(|block| block.segments.iter_mut())
+            .chain(sponsor_segments.iter_mut());
+        for seg in segments {
+            seg.loudness = loudness::summarize(&loudness_samples, seg.start_ms, seg.end_ms);
+        }
+    }
+
+    // ブロック内の境界の根拠
+    for block in &mut blocks {
+        block.boundaries = boundaries_in_block(block, &boundaries)
+            .map(BoundaryOutput::from)
+            .collect();
+        if use_black {
+            block.confidence = Some(black_confidence(block, &boundaries));
+        }
+    }
+
+    // 録画の余白にあるブロック（前後の番組のCM）と想定されるCM枠の数（ヒントファイルがある場合）
+    mark_padding_blocks(&mut blocks, config);
+    if let Some(expected) = config.expected_breaks {
+        let found = blocks.iter().filter(|b| b.padding.is_none()).count();
+        if found != expected {
+            progress!(verbose, "Found {} CM blocks in the program, expected {}", found, expected);
+        }
+    }
+
+    // 本編開始位置を推定（先頭のCMブロック・予定開始時刻・最初の無音区間）
+    let start_offset = detect_start_offset(&silence_segments, &blocks, config);
+    if let Some(start) = start_offset {
+        progress!(verbose, "Program starts at {}ms ({:?})", start.offset_ms, start.reason);
+    }
+    let start_offset_ms = start_offset.map(|start| start.offset_ms);
+
+    // 本編終了位置を推定（予定終了時刻・末尾のCMブロック・入力の終端）
+    let end_offset = detect_end_offset(&silence_segments, &blocks, start_offset_ms, parsed.duration_ms, config);
+    if let Some(end) = end_offset {
+        progress!(verbose, "Program ends at {}ms ({:?})", end.offset_ms, end.reason);
+    }
+    let end_offset_ms = end_offset.map(|end| end.offset_ms);
+
+    // 本編区間を計算（末尾は本編終了位置が分かる場合のみ）
+    let mut program_segments = compute_program_segments(&blocks, start_offset_ms, end_offset_ms);
+    if !channel_layout_changes.is_empty() {
+        program_segments = split_programs_at_channel_changes(program_segments, &channel_layout_changes);
+    }
+
+    Ok(Detection {
+        silence_segments: all_silence_segments,
+        black_segments,
+        scene_cuts,
+        channel_layout_changes,
+        log_anomalies,
+        epochs,
+        silence_threshold,
+        rescan_windows,
+        structure,
+        blocks,
+        sponsor_segments,
+        start_offset,
+        end_offset,
+        program_segments,
+    })
+}
+
+/// ヒントファイルと放送局のプロファイルを読み込み、設定に反映する
+/// プロファイルはデフォルトの設定に適用し、その上にコマンドライン引数を再度適用する
+fn apply_hints_and_profile(cli: CliArgs) -> Result<CliArgs, String> {
+    let hints = match &cli.hints_path {
+        Some(path) => Some(hints::read_hints(path)?),
+        None => None,
+    };
+    let profile_path = match (&cli.profile_path, &cli.profiles_dir, hints.as_ref().and_then(|h| h.channel_id.as_ref())) {
+        (Some(path), _, _) => Some(PathBuf::from(path)),
+        (None, Some(dir), Some(channel_id)) => {
+            let path = Path::new(dir).join(format!("{}.json", channel_id));
+            if path.exists() {
+                Some(path)
+            } else {
+                eprintln!("No profile for channel {} in {}", channel_id, dir);
+                None
+            }
+        }
+        _ => None,
+    };
+
+    let mut cli = match profile_path {
+        Some(path) => {
+            eprintln!("Using profile {}", path.display());
+            let mut base = CliArgs {
+                args: cli.args.clone(),
+                ..CliArgs::default()
+            };
+            profile::read_profile(&path)?.apply(&mut base.config)?;
+            parse_args_from(base, cli.args)?
+        }
+        None => cli,
+    };
+    if let Some(hints) = hints {
+        cli.config.scheduled_start_ms = hints.scheduled_start_ms;
+        cli.config.scheduled_end_ms = hints.scheduled_end_ms;
+        if cli.config.expected_breaks.is_none() {
+            cli.config.expected_breaks = hints.expected_breaks();
+        }
+    }
+    Ok(cli)
 }
 
-// タイムスタンプを抽出
-fn extract_timestamp(line: &str, key: &str) -> Option<f64> {
-    line.split(key)
-        .nth(1)?
-        .split_whitespace()
-        .next()?
-        .parse::<f64>()
-        .ok()
+/// 予定開始時刻より前・予定終了時刻より後の余白にあるブロック（前後の番組のCM）に印を付け、padding_weight を付与する
+fn mark_padding_blocks(blocks: &mut [CmBlock], config: &DetectorConfig) {
+    let start_ms = scheduled_start_offset_ms(config);
+    let end_ms = scheduled_end_offset_ms(config);
+    for block in blocks {
+        block.padding = if start_ms.is_some_and(|t| block.end_ms <= t + SCHEDULE_TOLERANCE_MS) {
+            Some(Padding::BeforeStart)
+        } else if end_ms.is_some_and(|t| block.start_ms >= t - SCHEDULE_TOLERANCE_MS) {
+            Some(Padding::AfterEnd)
+        } else {
+            None
+        };
+        block.padding_weight = block.padding.map(|_| PADDING_WEIGHT);
+    }
 }
 
-fn detect_start_offset_ms(silence_segments: &[SilenceSegment]) -> Option<i64> {
+/// CMブロックを検出（新アルゴリズム: 範囲ベース境界 + 短時間単位もチェーン継続）
+fn detect_blocks(silence_segments: &[SilenceSegment], config: &DetectorConfig, verbose: bool) -> Vec<CmBlock> {
+    let mut blocks = detect_blocks_range_based(silence_segments, config);
+    progress!(verbose, "Detected {} CM blocks (before merge)", blocks.len());
+
+    // 短時間単位による隣接ブロック統合（後処理）
+    blocks = merge_blocks_with_short_units(&blocks, silence_segments, config);
+    progress!(verbose, "After between-block merge: {} CM blocks", blocks.len());
+
+    // CMブロック境界の短時間単位を拡張（後処理）
+    blocks = extend_block_boundaries_with_short_units(&blocks, silence_segments, config);
+    progress!(verbose, "After boundary extension: {} CM blocks", blocks.len());
+    blocks
+}
+
+/// エポックごとにCMブロックを検出（不連続をまたいでチェーンしない）
+fn detect_epoch_blocks(
+    silence_segments: &[SilenceSegment],
+    epochs: &[Epoch],
+    config: &DetectorConfig,
+    verbose: bool,
+) -> Vec<CmBlock> {
+    if epochs.is_empty() {
+        return detect_blocks(silence_segments, config, verbose);
+    }
+    let mut blocks = Vec::new();
+    for epoch in epochs {
+        let epoch_segments: Vec<SilenceSegment> = silence_segments
+            .iter()
+            .filter(|s| (epoch.start_ms..=epoch.end_ms).contains(&s.center_ms()))
+            .cloned()
+            .collect();
+        blocks.extend(detect_blocks(&epoch_segments, config, verbose));
+    }
+    blocks
+}
+
+/// サイドカーファイルから録画開始時刻を読み込む
+/// RFC3339 文字列のみのテキスト、または {"recorded_at": "..."} 形式のJSON
+fn read_recorded_at_file(path: &str) -> Result<Timestamp, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read recorded-at file {}: {}", path, e))?;
+    let value = match serde_json::from_str::<serde_json::Value>(&content) {
+        Ok(json) => json
+            .get("recorded_at")
+            .and_then(|v| v.as_str())
+            .map(str::to_string)
+            .ok_or_else(|| format!("No recorded_at field in {}", path))?,
+        Err(_) => content,
+    };
+    Timestamp::parse_rfc3339(&value)
+        .ok_or_else(|| format!("Invalid RFC3339 timestamp in {}: {}", path, value.trim()))
+}
+
+/// 本編開始位置の推定の根拠
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum StartOffsetReason {
+    /// 探索範囲内の最初の無音区間
+    FirstSilence,
+    /// 録画の先頭付近から始まるCMブロックの終了位置（CMの途中から録画が始まった場合）
+    LeadingCmBlock,
+    /// 番組の予定開始時刻に最も近い無音区間・CMブロックの終了位置
+    ScheduledSilence,
+    /// 予定開始時刻（近くに無音区間がない場合）
+    Scheduled,
+}
+
+/// 本編開始位置の推定値
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct StartOffset {
+    offset_ms: i64,
+    reason: StartOffsetReason,
+}
+
+/// 探索範囲（config.start_offset_window）内の最初の無音区間の中心点
+fn detect_start_offset_ms(silence_segments: &[SilenceSegment], window: Range) -> Option<i64> {
     for seg in silence_segments {
-        let center_ms = (seg.start_ms + seg.end_ms) / 2;
-        if center_ms >= START_OFFSET_MIN_MS && center_ms <= START_OFFSET_MAX_MS {
+        let center_ms = seg.center_ms();
+        if (window.start..=window.end).contains(&center_ms) {
             return Some(center_ms);
         }
     }
     None
 }
 
+/// 本編開始位置を推定
+/// 1. 予定開始時刻が分かる場合: その前後 SCHEDULE_TOLERANCE_MS 以内の先頭CMブロックの終了位置・無音区間、なければ予定開始時刻
+/// 2. 探索範囲の終端より前から始まるCMブロックがあれば、その終了位置
+/// 3. 探索範囲内の最初の無音区間の中心点
+fn detect_start_offset(
+    silence_segments: &[SilenceSegment],
+    blocks: &[CmBlock],
+    config: &DetectorConfig,
+) -> Option<StartOffset> {
+    let leading_block_end = blocks
+        .first()
+        .filter(|b| b.start_ms <= config.start_offset_window.end)
+        .map(|b| b.end_ms);
+
+    if let Some(expected_ms) = scheduled_start_offset_ms(config) {
+        let near = |time_ms: &i64| (time_ms - expected_ms).abs() <= SCHEDULE_TOLERANCE_MS;
+        let found = leading_block_end.filter(near).or_else(|| {
+            silence_segments
+                .iter()
+                .map(SilenceSegment::center_ms)
+                .filter(near)
+                .min_by_key(|t| (t - expected_ms).abs())
+        });
+        return Some(match found {
+            Some(offset_ms) => StartOffset { offset_ms, reason: StartOffsetReason::ScheduledSilence },
+            None => StartOffset { offset_ms: expected_ms.max(0), reason: StartOffsetReason::Scheduled },
+        });
+    }
+
+    if let Some(offset_ms) = leading_block_end {
+        return Some(StartOffset { offset_ms, reason: StartOffsetReason::LeadingCmBlock });
+    }
+    detect_start_offset_ms(silence_segments, config.start_offset_window)
+        .map(|offset_ms| StartOffset { offset_ms, reason: StartOffsetReason::FirstSilence })
+}
+
+/// 予定開始時刻の録画先頭からの位置（ヒントファイル、または録画開始時刻が分かる場合）
+fn scheduled_start_offset_ms(config: &DetectorConfig) -> Option<i64> {
+    if config.scheduled_start_ms.is_some() {
+        return config.scheduled_start_ms;
+    }
+    let scheduled = config.scheduled_start?;
+    let recording = config.recording_start?;
+    Some(scheduled.unix_ms - recording.unix_ms)
+}
+
+/// 本編終了位置の推定の根拠
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum EndOffsetReason {
+    /// 予定終了時刻（または本編開始位置 + 番組の長さ）に最も近い、末尾のCMブロックの開始位置・無音区間
+    ScheduledSilence,
+    /// 予定終了時刻（近くに無音区間がない場合）
+    Scheduled,
+    /// 録画の終端付近で終わるCMブロックの開始位置
+    TrailingCmBlock,
+    /// 入力の終端
+    Duration,
+}
+
+/// 本編終了位置の推定値
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct EndOffset {
+    offset_ms: i64,
+    reason: EndOffsetReason,
+}
+
+/// 本編終了位置（末尾の余白・次番組の予告の手前）を推定
+/// 1. 予定終了時刻（なければ本編開始位置 + 番組の長さ）が分かる場合: その前後 SCHEDULE_TOLERANCE_MS 以内の
+///    末尾のCMブロックの開始位置・無音区間、なければ予定終了時刻
+/// 2. 録画の終端から end_offset_window_ms 以内で終わるCMブロックがあれば、その開始位置
+/// 3. 入力の終端（Duration が分かる場合）
+fn detect_end_offset(
+    silence_segments: &[SilenceSegment],
+    blocks: &[CmBlock],
+    start_offset_ms: Option<i64>,
+    duration_ms: Option<i64>,
+    config: &DetectorConfig,
+) -> Option<EndOffset> {
+    let trailing_block_start = duration_ms.and_then(|duration_ms| {
+        blocks
+            .last()
+            .filter(|b| duration_ms - b.end_ms <= config.end_offset_window_ms)
+            .map(|b| b.start_ms)
+    });
+
+    let expected_ms = scheduled_end_offset_ms(config)
+        .or_else(|| config.program_duration_ms.map(|d| start_offset_ms.unwrap_or(0) + d));
+    if let Some(expected_ms) = expected_ms {
+        let near = |time_ms: &i64| (time_ms - expected_ms).abs() <= SCHEDULE_TOLERANCE_MS;
+        let found = trailing_block_start.filter(near).or_else(|| {
+            silence_segments
+                .iter()
+                .map(SilenceSegment::center_ms)
+                .filter(near)
+                .min_by_key(|t| (t - expected_ms).abs())
+        });
+        return Some(match found {
+            Some(offset_ms) => EndOffset { offset_ms, reason: EndOffsetReason::ScheduledSilence },
+            None => EndOffset {
+                offset_ms: duration_ms.map_or(expected_ms, |d| expected_ms.min(d)),
+                reason: EndOffsetReason::Scheduled,
+            },
+        });
+    }
+
+    if let Some(offset_ms) = trailing_block_start {
+        return Some(EndOffset { offset_ms, reason: EndOffsetReason::TrailingCmBlock });
+    }
+    duration_ms.map(|offset_ms| EndOffset { offset_ms, reason: EndOffsetReason::Duration })
+}
+
+/// 予定終了時刻の録画先頭からの位置（ヒントファイル、または録画開始時刻が分かる場合）
+fn scheduled_end_offset_ms(config: &DetectorConfig) -> Option<i64> {
+    if config.scheduled_end_ms.is_some() {
+        return config.scheduled_end_ms;
+    }
+    let scheduled = config.scheduled_end?;
+    let recording = config.recording_start?;
+    Some(scheduled.unix_ms - recording.unix_ms)
+}
+
 /// 粗い標準単位数を決定（gap/15 を四捨五入）
 /// 例: 29s → 29/15 = 1.93 → 2単位 → 30s
 /// 例: 44s → 44/15 = 2.93 → 3単位 → 45s
@@ -238,107 +1707,134 @@ fn expected_interval_ms(gap_ms: i64) -> Option<i64> {
     Some((units as f64 * STANDARD_UNIT_SEC * 1000.0) as i64)
 }
 
-/// 短時間単位（5/10秒）かを判定
-fn is_short_unit(duration_sec: f64) -> bool {
-    let tolerance_sec = TOLERANCE_MS as f64 / 1000.0;
-    for unit in SHORT_UNITS {
-        if (duration_sec - unit).abs() <= tolerance_sec {
-            return true;
+
+/// 隣接する無音区間の間隔を単位として判定した結果
+/// 保持する範囲は次の判定に引き継ぐ境界点の候補範囲
+#[derive(Debug, Clone, Copy)]
+enum UnitMatch {
+    /// 標準単位（15秒倍数）
+    Standard(Range),
+    /// 短時間単位（5/10秒）
+    Short(Range),
+    /// ブリッジ区間（config.bridges のインデックス）
+    Bridge(Range, usize),
+}
+
+/// 短時間単位（5/10秒）に一致するか判定
+fn match_short_unit(metric: GapMetric, prev: &Range, curr: &Range, config: &DetectorConfig) -> Option<Range> {
+    config
+        .short_units
+        .iter()
+        .find_map(|unit| metric.accepts(prev, curr, (unit * 1000.0) as i64, config.tolerance_ms))
+}
+
+/// 隣接する無音区間の間隔を標準単位・短時間単位として判定
+/// 標準単位を優先し、一致しなければ短時間単位を試す
+/// 90s以上（6単位以上）の間隔はNoneを返す
+fn classify_gap(
+    prev: &Range,
+    curr: &Range,
+    standard_metric: GapMetric,
+    short_metric: GapMetric,
+    config: &DetectorConfig,
+) -> Option<UnitMatch> {
+    let expected_ms = expected_interval_ms(standard_metric.gap_ms(prev, curr))?;
+    if let Some(range) = standard_metric.accepts(prev, curr, expected_ms, config.tolerance_ms) {
+        return Some(UnitMatch::Standard(range));
+    }
+    match_short_unit(short_metric, prev, curr, config).map(UnitMatch::Short)
+}
+
+/// ブリッジ区間を許可する時刻かを判定
+/// 録画開始時刻が不明な場合は常に許可し、既知の場合は壁時計の :00/:30 前後のみ許可
+fn bridge_allowed_at(time_ms: i64, config: &DetectorConfig) -> bool {
+    match config.recording_start {
+        None => true,
+        Some(start) => {
+            let half_hour_sec = start.add_ms(time_ms).local_seconds_of_day() % 1800;
+            half_hour_sec.min(1800 - half_hour_sec) <= config.bridge_window_sec
         }
     }
-    false
 }
 
+/// ブリッジ区間に一致するか判定し、引き継ぐ範囲とブリッジ種別のインデックスを返す
+fn match_bridge(
+    metric: GapMetric,
+    prev: &Range,
+    curr: &Range,
+    config: &DetectorConfig,
+) -> Option<(Range, usize)> {
+    if !bridge_allowed_at((prev.center() + curr.center()) / 2, config) {
+        return None;
+    }
+    config.bridges.iter().enumerate().find_map(|(idx, bridge)| {
+        metric
+            .accepts(prev, curr, bridge.duration_ms, config.tolerance_ms)
+            .map(|range| (range, idx))
+    })
+}
+
+/// 中心点が center_ms に一致する無音区間のインデックスを探す
+/// （CMブロックの開始点・終了点は無音区間の中心点）
+fn find_silence_at_center(silence_segments: &[SilenceSegment], center_ms: i64) -> Option<usize> {
+    silence_segments.iter().position(|s| s.center_ms() == center_ms)
+}
 
 /// CMブロックを検出（範囲ベースアルゴリズム）
 /// 無音区間を範囲 [start, end] として扱い、範囲の交差で境界点を決定
 /// 短時間単位（5s/10s）もチェーンに含める（標準単位チェックは後処理で実施）
-fn detect_blocks_range_based(silence_segments: &[SilenceSegment]) -> Vec<CmBlock> {
+fn detect_blocks_range_based(
+    silence_segments: &[SilenceSegment],
+    config: &DetectorConfig,
+) -> Vec<CmBlock> {
     if silence_segments.len() < 2 {
         return Vec::new();
     }
 
+    let metrics = &config.gap_metrics;
     let mut blocks = Vec::new();
-    // (from_idx, to_idx, is_standard) - is_standard: 標準単位パスでマッチしたか
-    let mut chain_segments: Vec<(usize, usize, bool)> = Vec::new();
-    let mut prev_range = Range::new(silence_segments[0].start_ms, silence_segments[0].end_ms);
+    // (from_idx, to_idx, unit_match) - unit_match: どの単位としてマッチしたか
+    let mut chain_segments: Vec<(usize, usize, UnitMatch)> = Vec::new();
+    let mut prev_range = silence_segments[0].range();
 
     for i in 1..silence_segments.len() {
-        let curr = &silence_segments[i];
-        let curr_range = Range::new(curr.start_ms, curr.end_ms);
-
-        // 前後の無音区間の間隔を粗く評価
-        let prev_center = (prev_range.start + prev_range.end) / 2;
-        let curr_center = (curr_range.start + curr_range.end) / 2;
-        let gap_ms = curr_center - prev_center;
-        let gap_sec = gap_ms as f64 / 1000.0;
-
-        // 標準単位（15s倍数）または短時間単位（5s/10s）かをチェック
-        let expected_ms = match expected_interval_ms(gap_ms) {
-            Some(ms) => ms,
+        let curr_range = silence_segments[i].range();
+
+        // 標準単位（15s倍数）、短時間単位（5s/10s）、ブリッジ区間のいずれかをチェック
+        // 90s超過、またはいずれにも該当しない場合はチェーンを終了
+        let unit_match =
+            classify_gap(&prev_range, &curr_range, metrics.chain_standard, metrics.chain_short, config)
+                .or_else(|| {
+                    match_bridge(metrics.chain_standard, &prev_range, &curr_range, config)
+                        .map(|(range, idx)| UnitMatch::Bridge(range, idx))
+                });
+        match unit_match {
+            Some(unit_match) => {
+                // 交差あり - チェーンを継続
+                // 次イテレーションの prev_range は交差範囲
+                let (UnitMatch::Standard(valid_range)
+                | UnitMatch::Short(valid_range)
+                | UnitMatch::Bridge(valid_range, _)) = unit_match;
+                chain_segments.push((i - 1, i, unit_match));
+                prev_range = valid_range;
+            }
             None => {
-                // 90s超過 - チェーンを終了して評価
-                if let Some(block) = try_make_block_range_based(
-                    &chain_segments,
-                    silence_segments,
-                ) {
+                // 交差なし - チェーンを終了して評価
+                if let Some(block) =
+                    try_make_block_range_based(&chain_segments, silence_segments, config)
+                {
                     blocks.push(block);
                 }
+
+                // チェーンをリセット
                 chain_segments.clear();
                 prev_range = curr_range;
-                continue;
             }
-        };
-
-        // 期待範囲を計算: prev_range をオフセットして許容範囲を作る
-        let expected_range_low = prev_range.offset(expected_ms - TOLERANCE_MS);
-        let expected_range_high = prev_range.offset(expected_ms + TOLERANCE_MS);
-        let target_range = Range::new(expected_range_low.start, expected_range_high.end);
-
-        // 標準単位での交差を計算
-        let standard_match = curr_range.intersect(&target_range);
-
-        // 短時間単位でのマッチもチェック
-        let short_unit_match = if standard_match.is_none() && is_short_unit(gap_sec) {
-            // 短時間単位の場合、実際のギャップで交差範囲を計算
-            let short_expected_ms = (gap_sec * 1000.0).round() as i64;
-            let short_range_low = prev_range.offset(short_expected_ms - TOLERANCE_MS);
-            let short_range_high = prev_range.offset(short_expected_ms + TOLERANCE_MS);
-            let short_target = Range::new(short_range_low.start, short_range_high.end);
-            curr_range.intersect(&short_target)
-        } else {
-            None
-        };
-
-        // いずれかでマッチすればチェーン継続
-        // standard_match.is_some() なら標準単位パスでマッチ
-        let is_standard = standard_match.is_some();
-        if let Some(valid_range) = standard_match.or(short_unit_match) {
-            // 交差あり - チェーンを継続
-            chain_segments.push((i - 1, i, is_standard));
-
-            // 次イテレーションの prev_range は交差範囲
-            prev_range = valid_range;
-        } else {
-            // 交差なし - チェーンを終了して評価
-            if let Some(block) = try_make_block_range_based(
-                &chain_segments,
-                silence_segments,
-            ) {
-                blocks.push(block);
-            }
-
-            // チェーンをリセット
-            chain_segments.clear();
-            prev_range = curr_range;
         }
     }
 
     // 最後のチェーンを評価
-    if let Some(block) = try_make_block_range_based(
-        &chain_segments,
-        silence_segments,
-    ) {
+    if let Some(block) = try_make_block_range_based(&chain_segments, silence_segments, config) {
         blocks.push(block);
     }
 
@@ -349,8 +1845,9 @@ fn detect_blocks_range_based(silence_segments: &[SilenceSegment]) -> Vec<CmBlock
 /// 出力点選定: 開始点・終了点 = 無音区間の中心点
 /// 注: 標準単位数・最小時間のチェックは後処理（filter_blocks_by_standard_units）で実施
 fn try_make_block_range_based(
-    chain_segments: &[(usize, usize, bool)], // (from_idx, to_idx, is_standard)
+    chain_segments: &[(usize, usize, UnitMatch)], // (from_idx, to_idx, unit_match)
     silence_segments: &[SilenceSegment],
+    config: &DetectorConfig,
 ) -> Option<CmBlock> {
     if chain_segments.is_empty() {
         return None;
@@ -366,38 +1863,34 @@ fn try_make_block_range_based(
     // 出力点選定:
     // - 開始点 = 最初の無音区間の中心点
     // - 終了点 = 最後の無音区間の中心点
-    let start_ms = (first_silence.start_ms + first_silence.end_ms) / 2;
-    let end_ms = (last_silence.start_ms + last_silence.end_ms) / 2;
+    let start_ms = first_silence.center_ms();
+    let end_ms = last_silence.center_ms();
 
     let total_duration_ms = end_ms - start_ms;
     let total_duration_sec = total_duration_ms as f64 / 1000.0;
 
     // 360秒以下のサニティチェックのみ（他は後処理で確認）
     if total_duration_sec <= MAX_BLOCK_DURATION_SEC && total_duration_sec > 0.0 {
-        // セグメント情報を生成
+        // セグメント情報を生成（境界は chain_segment の計測方法に従う）
         let mut segments: Vec<CmCandidate> = Vec::new();
-        for (from_idx, to_idx, is_standard) in chain_segments {
+        for (from_idx, to_idx, unit_match) in chain_segments {
             let from_silence = &silence_segments[*from_idx];
             let to_silence = &silence_segments[*to_idx];
-            // 各セグメント: from の end から to の start まで
-            let seg_start = from_silence.end_ms;
-            let seg_end = to_silence.start_ms;
-            let duration_sec = (seg_end - seg_start) as f64 / 1000.0;
-
-            segments.push(CmCandidate {
-                start_ms: seg_start,
-                end_ms: seg_end,
-                duration_sec,
-                is_standard: *is_standard,
+            let (seg_start, seg_end) = config
+                .gap_metrics
+                .chain_segment
+                .segment_bounds(&from_silence.range(), &to_silence.range());
+
+            segments.push(match unit_match {
+                UnitMatch::Standard(_) => CmCandidate::new(seg_start, seg_end, true, SegmentKind::Cm),
+                UnitMatch::Short(_) => CmCandidate::new(seg_start, seg_end, false, SegmentKind::Cm),
+                UnitMatch::Bridge(_, idx) => {
+                    CmCandidate::bridge(seg_start, seg_end, &config.bridges[*idx])
+                }
             });
         }
 
-        Some(CmBlock {
-            start_ms,
-            end_ms,
-            duration_sec: total_duration_sec,
-            segments,
-        })
+        Some(CmBlock::new(start_ms, end_ms, segments))
     } else {
         None
     }
@@ -408,49 +1901,46 @@ fn try_make_block_range_based(
 fn merge_blocks_with_short_units(
     blocks: &[CmBlock],
     silence_segments: &[SilenceSegment],
+    config: &DetectorConfig,
 ) -> Vec<CmBlock> {
     if blocks.len() < 2 {
         return blocks.to_vec();
     }
 
+    let metric = config.gap_metrics.merge;
     let mut merged: Vec<CmBlock> = Vec::new();
     let mut current_block = blocks[0].clone();
 
-    for i in 1..blocks.len() {
-        let next_block = &blocks[i];
-
+    for next_block in blocks.iter().skip(1) {
         // 現在のブロックと次のブロックの間にある無音区間を探す
-        // ブロック間のギャップを計算
-        let gap_start = current_block.end_ms;
-        let gap_end = next_block.start_ms;
-
-        // ギャップ内の無音区間を見つけて短時間単位チェック
-        let can_merge = check_short_units_in_gap(silence_segments, gap_start, gap_end);
+        // ブロック境界の無音区間が見つからない場合は境界点そのものを範囲とする
+        let gap_start_range = find_silence_at_center(silence_segments, current_block.end_ms)
+            .map(|idx| silence_segments[idx].range())
+            .unwrap_or(Range::new(current_block.end_ms, current_block.end_ms));
+        let gap_end_range = find_silence_at_center(silence_segments, next_block.start_ms)
+            .map(|idx| silence_segments[idx].range())
+            .unwrap_or(Range::new(next_block.start_ms, next_block.start_ms));
+
+        // ギャップ内の無音区間を通る短時間単位チェーンを構築
+        let gap_chain = chain_short_units_in_gap(
+            silence_segments,
+            &gap_start_range,
+            &gap_end_range,
+            metric,
+            config,
+        );
 
-        if can_merge {
+        if let Some(gap_segments) = gap_chain {
             // ブロックを統合
             let mut merged_segments = current_block.segments.clone();
 
-            // ギャップ部分をセグメントとして追加（短時間単位なので is_standard: false）
-            let gap_duration = (gap_end - gap_start) as f64 / 1000.0;
-            merged_segments.push(CmCandidate {
-                start_ms: gap_start,
-                end_ms: gap_end,
-                duration_sec: gap_duration,
-                is_standard: false,
-            });
+            // ギャップ内のチェーンをセグメントとして追加（短時間単位なので is_standard: false）
+            merged_segments.extend(gap_segments);
 
             // 次のブロックのセグメントを追加
             merged_segments.extend(next_block.segments.clone());
 
-            let total_duration = (next_block.end_ms - current_block.start_ms) as f64 / 1000.0;
-
-            current_block = CmBlock {
-                start_ms: current_block.start_ms,
-                end_ms: next_block.end_ms,
-                duration_sec: total_duration,
-                segments: merged_segments,
-            };
+            current_block = CmBlock::new(current_block.start_ms, next_block.end_ms, merged_segments);
         } else {
             // 統合しない - 現在のブロックを確定
             merged.push(current_block);
@@ -464,39 +1954,41 @@ fn merge_blocks_with_short_units(
     merged
 }
 
-/// ギャップ内の無音区間を使って短時間単位チェーンが作れるか確認
-fn check_short_units_in_gap(
+/// ギャップ内の無音区間を通る短時間単位チェーンを構築
+/// gap_start_range / gap_end_range はギャップ両端の無音区間
+/// detect_blocks_range_based と同様に、隣接する無音区間の間隔を順に判定し、
+/// 一致した範囲を次の判定に引き継ぐ。全ての間隔が短時間単位であればチェーンのセグメントを返す
+fn chain_short_units_in_gap(
     silence_segments: &[SilenceSegment],
-    gap_start: i64,
-    gap_end: i64,
-) -> bool {
-    // ギャップ内にある無音区間を収集
-    let gap_silences: Vec<&SilenceSegment> = silence_segments
+    gap_start_range: &Range,
+    gap_end_range: &Range,
+    metric: GapMetric,
+    config: &DetectorConfig,
+) -> Option<Vec<CmCandidate>> {
+    let gap_start = gap_start_range.center();
+    let gap_end = gap_end_range.center();
+
+    // ギャップ内にある無音区間を収集し、終端の無音区間を加える
+    let mut chain_ranges: Vec<Range> = silence_segments
         .iter()
         .filter(|s| s.start_ms >= gap_start && s.end_ms <= gap_end)
+        .map(|s| s.range())
         .collect();
+    chain_ranges.push(*gap_end_range);
 
-    if gap_silences.is_empty() {
-        // 無音区間がない場合、ギャップ全体が短時間単位かチェック
-        let gap_sec = (gap_end - gap_start) as f64 / 1000.0;
-        return is_short_unit(gap_sec);
-    }
-
-    // 無音区間がある場合、連続する短時間単位でチェーンが作れるか確認
-    // 簡略化: ギャップ全体の長さで判定
-    let total_gap_sec = (gap_end - gap_start) as f64 / 1000.0;
-
-    // 短時間単位の組み合わせで表現できるかチェック（5秒または10秒の倍数±許容範囲）
-    for n in 1..=6 {
-        for unit in SHORT_UNITS {
-            let expected = unit * n as f64;
-            if (total_gap_sec - expected).abs() <= (TOLERANCE_MS as f64 / 1000.0) * n as f64 {
-                return true;
-            }
-        }
+    let mut segments = Vec::new();
+    let mut from_range = *gap_start_range;
+    let mut prev_range = *gap_start_range;
+    for curr_range in chain_ranges {
+        // 1つでも短時間単位でない間隔があればチェーンは作れない
+        let valid_range = match_short_unit(metric, &prev_range, &curr_range, config)?;
+        let (seg_start, seg_end) = metric.segment_bounds(&from_range, &curr_range);
+        segments.push(CmCandidate::new(seg_start, seg_end, false, SegmentKind::Cm));
+        from_range = curr_range;
+        prev_range = valid_range;
     }
 
-    false
+    Some(segments)
 }
 
 /// CMブロックの境界にある短時間単位を拡張する（後処理）
@@ -505,6 +1997,7 @@ fn check_short_units_in_gap(
 fn extend_block_boundaries_with_short_units(
     blocks: &[CmBlock],
     silence_segments: &[SilenceSegment],
+    config: &DetectorConfig,
 ) -> Vec<CmBlock> {
     if blocks.is_empty() || silence_segments.is_empty() {
         return blocks.to_vec();
@@ -512,50 +2005,54 @@ fn extend_block_boundaries_with_short_units(
 
     blocks
         .iter()
-        .map(|block| extend_single_block_boundaries(block, silence_segments))
+        .map(|block| extend_single_block_boundaries(block, silence_segments, config))
         .collect()
 }
 
-/// 単一ブロックの境界を短時間単位で拡張
+/// ブロック境界の外側の間隔が短時間単位またはブリッジ区間であれば、そのセグメントを返す
+fn match_edge_unit(
+    prev: &Range,
+    curr: &Range,
+    metric: GapMetric,
+    segment_metric: GapMetric,
+    config: &DetectorConfig,
+) -> Option<CmCandidate> {
+    let (seg_start, seg_end) = segment_metric.segment_bounds(prev, curr);
+    if match_short_unit(metric, prev, curr, config).is_some() {
+        return Some(CmCandidate::new(seg_start, seg_end, false, SegmentKind::Cm));
+    }
+    match_bridge(metric, prev, curr, config)
+        .map(|(_, idx)| CmCandidate::bridge(seg_start, seg_end, &config.bridges[idx]))
+}
+
+/// 単一ブロックの境界を短時間単位・ブリッジ区間で拡張
 fn extend_single_block_boundaries(
     block: &CmBlock,
     silence_segments: &[SilenceSegment],
+    config: &DetectorConfig,
 ) -> CmBlock {
+    let metric = config.gap_metrics.extension;
+    let segment_metric = config.gap_metrics.extension_segment;
     let mut new_start_ms = block.start_ms;
     let mut new_end_ms = block.end_ms;
     let mut prepend_segments: Vec<CmCandidate> = Vec::new();
     let mut append_segments: Vec<CmCandidate> = Vec::new();
 
     // ブロック開始点に対応する無音区間を探す（中心点 == block.start_ms）
-    if let Some(start_idx) = silence_segments
-        .iter()
-        .position(|s| (s.start_ms + s.end_ms) / 2 == block.start_ms)
-    {
+    if let Some(start_idx) = find_silence_at_center(silence_segments, block.start_ms) {
         // 前方に短時間単位を探す
         let mut current_idx = start_idx;
         while current_idx > 0 {
-            let prev_seg = &silence_segments[current_idx - 1];
-            let curr_seg = &silence_segments[current_idx];
-            // prev の end から curr の start までのギャップ
-            let gap_ms = curr_seg.start_ms - prev_seg.end_ms;
-            let gap_sec = gap_ms as f64 / 1000.0;
-
-            if is_short_unit(gap_sec) {
-                // 短時間単位を先頭に追加（is_standard: false）
-                // セグメントの境界は無音区間の中心点を使用
-                let seg_start = (prev_seg.start_ms + prev_seg.end_ms) / 2;
-                let seg_end = (curr_seg.start_ms + curr_seg.end_ms) / 2;
-                let seg_duration_sec = (seg_end - seg_start) as f64 / 1000.0;
-                prepend_segments.insert(
-                    0,
-                    CmCandidate {
-                        start_ms: seg_start,
-                        end_ms: seg_end,
-                        duration_sec: seg_duration_sec,
-                        is_standard: false,
-                    },
-                );
-                new_start_ms = seg_start;
+            let prev_range = silence_segments[current_idx - 1].range();
+            let curr_range = silence_segments[current_idx].range();
+
+            if let Some(segment) =
+                match_edge_unit(&prev_range, &curr_range, metric, segment_metric, config)
+            {
+                // 短時間単位・ブリッジ区間を先頭に追加（is_standard: false）
+                prepend_segments.insert(0, segment);
+                // ブロックの開始点は常に無音区間の中心点
+                new_start_ms = prev_range.center();
                 current_idx -= 1;
             } else {
                 break;
@@ -564,32 +2061,20 @@ fn extend_single_block_boundaries(
     }
 
     // ブロック終了点に対応する無音区間を探す（中心点 == block.end_ms）
-    if let Some(end_idx) = silence_segments
-        .iter()
-        .position(|s| (s.start_ms + s.end_ms) / 2 == block.end_ms)
-    {
+    if let Some(end_idx) = find_silence_at_center(silence_segments, block.end_ms) {
         // 後方に短時間単位を探す
         let mut current_idx = end_idx;
         while current_idx + 1 < silence_segments.len() {
-            let curr_seg = &silence_segments[current_idx];
-            let next_seg = &silence_segments[current_idx + 1];
-            // curr の end から next の start までのギャップ
-            let gap_ms = next_seg.start_ms - curr_seg.end_ms;
-            let gap_sec = gap_ms as f64 / 1000.0;
-
-            if is_short_unit(gap_sec) {
-                // 短時間単位を末尾に追加（is_standard: false）
-                // セグメントの境界は無音区間の中心点を使用
-                let seg_start = (curr_seg.start_ms + curr_seg.end_ms) / 2;
-                let seg_end = (next_seg.start_ms + next_seg.end_ms) / 2;
-                let seg_duration_sec = (seg_end - seg_start) as f64 / 1000.0;
-                append_segments.push(CmCandidate {
-                    start_ms: seg_start,
-                    end_ms: seg_end,
-                    duration_sec: seg_duration_sec,
-                    is_standard: false,
-                });
-                new_end_ms = seg_end;
+            let curr_range = silence_segments[current_idx].range();
+            let next_range = silence_segments[current_idx + 1].range();
+
+            if let Some(segment) =
+                match_edge_unit(&curr_range, &next_range, metric, segment_metric, config)
+            {
+                // 短時間単位・ブリッジ区間を末尾に追加（is_standard: false）
+                append_segments.push(segment);
+                // ブロックの終了点は常に無音区間の中心点
+                new_end_ms = next_range.center();
                 current_idx += 1;
             } else {
                 break;
@@ -607,32 +2092,419 @@ fn extend_single_block_boundaries(
     new_segments.extend(block.segments.clone());
     new_segments.extend(append_segments);
 
-    let new_duration_sec = (new_end_ms - new_start_ms) as f64 / 1000.0;
+    CmBlock::new(new_start_ms, new_end_ms, new_segments)
+}
+
+/// 各入力の境界候補を統合（evidence::fuse）
+/// 黒画面の require モードでは、黒画面を伴わない境界候補を除外する
+fn fuse_boundaries(
+    silence_segments: &[SilenceSegment],
+    black_segments: &[BlackSegment],
+    scene_cuts: &[i64],
+    loudness_samples: &[LoudnessSample],
+    channel_layout_changes: &[ChannelLayoutChange],
+    config: &DetectorConfig,
+) -> Vec<FusedBoundary> {
+    let silence = SilenceEvidence(silence_segments);
+    let black = BlackEvidence(black_segments);
+    let scene = SceneCutEvidence {
+        cuts: scene_cuts,
+        snap: config.snap_to_scene_cuts,
+    };
+    let loudness = LoudnessStepEvidence::new(loudness_samples);
+    let channel = ChannelChangeEvidence(channel_layout_changes);
+
+    let mut sources: Vec<&dyn BoundaryEvidence> = vec![&silence, &channel, &scene, &loudness];
+    if config.black_mode != BlackMode::Off {
+        sources.push(&black);
+    }
+    let boundaries = evidence::fuse(&sources);
+
+    if config.black_mode == BlackMode::Require && !black_segments.is_empty() {
+        boundaries.into_iter().filter(|b| b.has(Signal::Black)).collect()
+    } else {
+        boundaries
+    }
+}
+
+/// 中心点がブロック内にある境界候補
+fn boundaries_in_block<'a>(
+    block: &CmBlock,
+    boundaries: &'a [FusedBoundary],
+) -> impl Iterator<Item = &'a FusedBoundary> {
+    let range = block.start_ms..=block.end_ms;
+    boundaries
+        .iter()
+        .filter(move |b| range.contains(&b.range.center()))
+}
+
+/// CMブロックの境界のうち、黒画面を伴うものの割合
+fn black_confidence(block: &CmBlock, boundaries: &[FusedBoundary]) -> f64 {
+    let (total, confirmed) = boundaries_in_block(block, boundaries)
+        .fold((0, 0), |(total, confirmed), b| {
+            (total + 1, confirmed + b.has(Signal::Black) as usize)
+        });
+    if total == 0 {
+        return 0.0;
+    }
+    confirmed as f64 / total as f64
+}
+
+/// 本編区間を計算
+/// 本編開始（start_offset_ms、なければ0）からCMブロックを除いた区間。末尾は duration_ms が分かる場合のみ
+fn compute_program_segments(
+    blocks: &[CmBlock],
+    start_offset_ms: Option<i64>,
+    end_offset_ms: Option<i64>,
+) -> Vec<ProgramSegment> {
+    let make_segment = |start_ms: i64, end_ms: i64| ProgramSegment {
+        start_ms,
+        end_ms,
+        duration_sec: (end_ms - start_ms) as f64 / 1000.0,
+        start_at: None,
+        end_at: None,
+        channel_layout: None,
+    };
+
+    let mut segments = Vec::new();
+    let mut cursor = start_offset_ms.unwrap_or(0).max(0);
+    let limit = end_offset_ms.unwrap_or(i64::MAX);
+    for block in blocks {
+        let end_ms = block.start_ms.min(limit);
+        if end_ms > cursor {
+            segments.push(make_segment(cursor, end_ms));
+        }
+        cursor = cursor.max(block.end_ms);
+    }
+    if let Some(end_offset_ms) = end_offset_ms
+        && end_offset_ms > cursor
+    {
+        segments.push(make_segment(cursor, end_offset_ms));
+    }
+    segments
+}
+
+/// チャンネルレイアウトの変化をブロック内部（境界から CHANNEL_CHANGE_WINDOW_MS より内側）に含むブロックを、
+/// 変化に最も近いセグメント境界で分割する
+/// 本編とCMはレイアウトが異なることが多く、変化をまたぐブロックは本編を取り込んでいる
+fn split_blocks_at_channel_changes(
+    blocks: Vec<CmBlock>,
+    changes: &[ChannelLayoutChange],
+    verbose: bool,
+) -> Vec<CmBlock> {
+    let mut result = Vec::new();
+    let mut pending: Vec<CmBlock> = blocks.into_iter().rev().collect();
+    while let Some(block) = pending.pop() {
+        let inner = (block.start_ms + CHANNEL_CHANGE_WINDOW_MS)..(block.end_ms - CHANNEL_CHANGE_WINDOW_MS);
+        let split = changes
+            .iter()
+            .find(|change| inner.contains(&change.time_ms))
+            .and_then(|change| {
+                (1..block.segments.len()).min_by_key(|&i| (block.segments[i].start_ms - change.time_ms).abs())
+            });
+        let Some(i) = split else {
+            result.push(block);
+            continue;
+        };
+
+        // 分割点は前後のセグメントの間（無音区間の中心付近）
+        let split_ms = (block.segments[i - 1].end_ms + block.segments[i].start_ms) / 2;
+        progress!(
+            verbose,
+            "Split block {}-{}ms at {}ms: channel layout changed",
+            block.start_ms, block.end_ms, split_ms
+        );
+        let mut segments = block.segments;
+        let tail = segments.split_off(i);
+        // 後半にも変化が残っている可能性があるため再処理する
+        pending.push(CmBlock::new(split_ms, block.end_ms, tail));
+        result.push(CmBlock::new(block.start_ms, split_ms, segments));
+    }
+    result
+}
+
+/// 本編区間をチャンネルレイアウトの変化点で分割し、各区間のレイアウトを付与
+/// changes は時刻順
+fn split_programs_at_channel_changes(
+    programs: Vec<ProgramSegment>,
+    changes: &[ChannelLayoutChange],
+) -> Vec<ProgramSegment> {
+    let layout_at = |time_ms: i64| {
+        let idx = changes.partition_point(|c| c.time_ms <= time_ms);
+        match idx {
+            0 => changes.first().map(|c| c.from.clone()),
+            _ => Some(changes[idx - 1].to.clone()),
+        }
+    };
+
+    let mut result = Vec::new();
+    for program in programs {
+        let mut cursor = program.start_ms;
+        let cuts = changes
+            .iter()
+            .map(|c| c.time_ms)
+            .filter(|&t| t > program.start_ms && t < program.end_ms)
+            .chain([program.end_ms]);
+        for end_ms in cuts {
+            result.push(ProgramSegment {
+                start_ms: cursor,
+                end_ms,
+                duration_sec: (end_ms - cursor) as f64 / 1000.0,
+                start_at: None,
+                end_at: None,
+                channel_layout: layout_at(cursor),
+            });
+            cursor = end_ms;
+        }
+    }
+    result
+}
+
+/// CMブロックと本編区間に壁時計時刻（start_at/end_at）を付与
+fn annotate_wall_clock(blocks: &mut [CmBlock], programs: &mut [ProgramSegment], start: Timestamp) {
+    let at = |ms: i64| Some(start.add_ms(ms).to_rfc3339());
+    for block in blocks {
+        block.start_at = at(block.start_ms);
+        block.end_at = at(block.end_ms);
+    }
+    for program in programs {
+        program.start_at = at(program.start_ms);
+        program.end_at = at(program.end_ms);
+    }
+}
+
+/// ブロック内の標準単位数をカウント（is_standard フラグを使用）
+fn count_standard_units(block: &CmBlock) -> usize {
+    block.segments.iter().filter(|seg| seg.is_standard).count()
+}
+
+/// 最終フィルタ: 標準単位数と最小時間を満たすブロックのみを残す
+/// このチェックは全てのマージ・拡張処理後に実行される
+fn filter_blocks_by_standard_units(blocks: Vec<CmBlock>, config: &DetectorConfig) -> Vec<CmBlock> {
+    blocks.into_iter().filter(|b| passes_standard_units(b, config)).collect()
+}
+
+/// 最終フィルタの条件（合計 min_block_duration_sec 以上、標準単位 min_standard_units 個以上）
+fn passes_standard_units(block: &CmBlock, config: &DetectorConfig) -> bool {
+    let standard_count = count_standard_units(block);
+    let meets_duration = block.duration_sec >= config.min_block_duration_sec;
+    let meets_standard_units = standard_count >= config.min_standard_units;
+
+    meets_duration && meets_standard_units
+}
+
+/// 提供クレジットの長さ（5〜10秒 ±許容範囲）かを判定
+fn is_sponsor_duration(duration_sec: f64) -> bool {
+    let tolerance_sec = TOLERANCE_MS as f64 / 1000.0;
+    duration_sec >= SPONSOR_MIN_SEC - tolerance_sec && duration_sec <= SPONSOR_MAX_SEC + tolerance_sec
+}
+
+/// CMブロック端の提供クレジット
+enum SponsorEdge {
+    /// ブロック端のセグメント（短時間単位として取り込み済み）
+    Absorbed,
+    /// ブロック外側に隣接する区間（対象となる無音区間のインデックス）
+    Adjacent(usize),
+}
+
+/// 境界が無音以外の信号（黒画面・シーンチェンジ・ラウドネスの段差）に支持されているか
+fn has_sponsor_evidence(boundaries: &[FusedBoundary], idx: Option<usize>) -> bool {
+    idx.and_then(|idx| boundaries.get(idx)).is_some_and(|boundary| {
+        boundary.has(Signal::Black) || boundary.has(Signal::SceneCut) || boundary.has(Signal::LoudnessStep)
+    })
+}
+
+/// CMブロックの前後にある提供クレジットを分類（後処理）
+/// ブロック端の非標準セグメント、またはブロック外側に隣接する5〜10秒の区間を sponsor とし、
+/// ポリシーに応じてブロックに含める・除外する・別に出力する。
+/// 5/10秒のCMと長さでは区別できないため、ブロック端のセグメントは両端のいずれかの境界が
+/// 無音以外の信号に支持される場合（または sponsor_edge_by_duration の場合）のみ sponsor とする。
+/// 外側に隣接する区間は短時間単位ではない（本編の可能性がある）ため、absorb_adjacent_sponsors の場合のみ
+/// ブロックに含め、それ以外は sponsor_segments に出力するのみとする。
+/// 端のセグメントを除外したブロックは最終フィルタを再確認し、満たさないものは除外する
+fn classify_sponsor_segments(
+    blocks: Vec<CmBlock>,
+    boundaries: &[FusedBoundary],
+    config: &DetectorConfig,
+) -> (Vec<CmBlock>, Vec<CmCandidate>) {
+    let silence_segments: Vec<SilenceSegment> = boundaries.iter().map(FusedBoundary::to_silence_segment).collect();
+    let silence_segments = silence_segments.as_slice();
+    let policy = config.sponsor_policy;
+    let metric = config.gap_metrics.extension;
+    let segment_metric = config.gap_metrics.extension_segment;
+    let mut sponsors = Vec::new();
+
+    let blocks = blocks
+        .into_iter()
+        .filter_map(|mut block| {
+            let start_idx = find_silence_at_center(silence_segments, block.start_ms);
+            let end_idx = find_silence_at_center(silence_segments, block.end_ms);
+            let leading_evidence = config.sponsor_edge_by_duration
+                || has_sponsor_evidence(boundaries, start_idx)
+                || has_sponsor_evidence(boundaries, start_idx.map(|idx| idx + 1));
+            let trailing_evidence = config.sponsor_edge_by_duration
+                || has_sponsor_evidence(boundaries, end_idx)
+                || has_sponsor_evidence(boundaries, end_idx.and_then(|idx| idx.checked_sub(1)));
+
+            // 先頭側
+            let leading = match block.segments.first() {
+                Some(seg)
+                    if leading_evidence
+                        && seg.kind == SegmentKind::Cm
+                        && !seg.is_standard
+                        && is_sponsor_duration(seg.duration_sec) =>
+                {
+                    Some(SponsorEdge::Absorbed)
+                }
+                _ => start_idx.filter(|&idx| idx > 0).and_then(|idx| {
+                    let gap_ms = metric.gap_ms(
+                        &silence_segments[idx - 1].range(),
+                        &silence_segments[idx].range(),
+                    );
+                    is_sponsor_duration(gap_ms as f64 / 1000.0).then_some(SponsorEdge::Adjacent(idx - 1))
+                }),
+            };
+
+            // 末尾側（先頭と同じセグメントを二重に扱わない）
+            let trailing = match block.segments.last() {
+                Some(seg)
+                    if trailing_evidence
+                        && block.segments.len() > 1
+                        && seg.kind == SegmentKind::Cm
+                        && !seg.is_standard
+                        && is_sponsor_duration(seg.duration_sec) =>
+                {
+                    Some(SponsorEdge::Absorbed)
+                }
+                _ => end_idx
+                    .filter(|&idx| idx + 1 < silence_segments.len())
+                    .and_then(|idx| {
+                        let gap_ms = metric.gap_ms(
+                            &silence_segments[idx].range(),
+                            &silence_segments[idx + 1].range(),
+                        );
+                        is_sponsor_duration(gap_ms as f64 / 1000.0)
+                            .then_some(SponsorEdge::Adjacent(idx + 1))
+                    }),
+            };
+
+            // 提供クレジットを除外した場合はブロックを最終フィルタで再確認する
+            let trimmed = policy != SponsorPolicy::Cm
+                && (matches!(leading, Some(SponsorEdge::Absorbed)) || matches!(trailing, Some(SponsorEdge::Absorbed)));
+            let mut found = Vec::new();
+            match leading {
+                Some(SponsorEdge::Absorbed) => {
+                    if policy == SponsorPolicy::Cm {
+                        block.segments[0].kind = SegmentKind::Sponsor;
+                    } else {
+                        let mut seg = block.segments.remove(0);
+                        // 新しい開始点は提供クレジット直後の無音区間の中心点
+                        block.start_ms = start_idx
+                            .and_then(|idx| silence_segments.get(idx + 1))
+                            .map(|s| s.center_ms())
+                            .unwrap_or(seg.end_ms);
+                        if policy == SponsorPolicy::Separate {
+                            seg.kind = SegmentKind::Sponsor;
+                            found.push(seg);
+                        }
+                    }
+                }
+                Some(SponsorEdge::Adjacent(idx)) => {
+                    let (seg_start, seg_end) = segment_metric.segment_bounds(
+                        &silence_segments[idx].range(),
+                        &silence_segments[idx + 1].range(),
+                    );
+                    let seg = CmCandidate::new(seg_start, seg_end, false, SegmentKind::Sponsor);
+                    match policy {
+                        SponsorPolicy::Cm if config.absorb_adjacent_sponsors => {
+                            block.start_ms = silence_segments[idx].center_ms();
+                            block.segments.insert(0, seg);
+                        }
+                        SponsorPolicy::Cm | SponsorPolicy::Separate => found.push(seg),
+                        SponsorPolicy::Program => {}
+                    }
+                }
+                None => {}
+            }
 
-    CmBlock {
-        start_ms: new_start_ms,
-        end_ms: new_end_ms,
-        duration_sec: new_duration_sec,
-        segments: new_segments,
-    }
-}
+            match trailing {
+                Some(SponsorEdge::Absorbed) => {
+                    if policy == SponsorPolicy::Cm {
+                        block.segments.last_mut().unwrap().kind = SegmentKind::Sponsor;
+                    } else {
+                        let mut seg = block.segments.pop().unwrap();
+                        // 新しい終了点は提供クレジット直前の無音区間の中心点
+                        block.end_ms = end_idx
+                            .and_then(|idx| idx.checked_sub(1))
+                            .map(|idx| silence_segments[idx].center_ms())
+                            .unwrap_or(seg.start_ms);
+                        if policy == SponsorPolicy::Separate {
+                            seg.kind = SegmentKind::Sponsor;
+                            found.push(seg);
+                        }
+                    }
+                }
+                Some(SponsorEdge::Adjacent(idx)) => {
+                    let (seg_start, seg_end) = segment_metric.segment_bounds(
+                        &silence_segments[idx - 1].range(),
+                        &silence_segments[idx].range(),
+                    );
+                    let seg = CmCandidate::new(seg_start, seg_end, false, SegmentKind::Sponsor);
+                    match policy {
+                        SponsorPolicy::Cm if config.absorb_adjacent_sponsors => {
+                            block.end_ms = silence_segments[idx].center_ms();
+                            block.segments.push(seg);
+                        }
+                        SponsorPolicy::Cm | SponsorPolicy::Separate => found.push(seg),
+                        SponsorPolicy::Program => {}
+                    }
+                }
+                None => {}
+            }
 
-/// ブロック内の標準単位数をカウント（is_standard フラグを使用）
-fn count_standard_units(block: &CmBlock) -> usize {
-    block.segments.iter().filter(|seg| seg.is_standard).count()
+            block.duration_sec = (block.end_ms - block.start_ms) as f64 / 1000.0;
+            // 番組構成のモデルで救済されたブロックは元々最終フィルタを満たさないため、空でなければ残す
+            if trimmed
+                && (block.segments.is_empty()
+                    || block.end_ms <= block.start_ms
+                    || (block.structure != Some(BreakFit::Rescued) && !passes_standard_units(&block, config)))
+            {
+                return None;
+            }
+            sponsors.extend(found);
+            Some(block)
+        })
+        .collect();
+
+    sponsors.sort_by_key(|seg| seg.start_ms);
+    (blocks, sponsors)
 }
 
-/// 最終フィルタ: 標準単位数と最小時間を満たすブロックのみを残す
-/// このチェックは全てのマージ・拡張処理後に実行される
-fn filter_blocks_by_standard_units(blocks: Vec<CmBlock>) -> Vec<CmBlock> {
+/// ブロック内のラウドネスのダイナミクスが本編らしいブロックを除外
+/// CMは個別にラウドネス調整・コンプレッサー処理されるため、ブロック内の幅は狭い
+fn filter_blocks_by_loudness(
+    blocks: Vec<CmBlock>,
+    loudness_samples: &[LoudnessSample],
+    config: &DetectorConfig,
+    verbose: bool,
+) -> Vec<CmBlock> {
+    let Some(max_range_lu) = config.max_cm_loudness_range_lu else {
+        return blocks;
+    };
     blocks
         .into_iter()
         .filter(|block| {
-            let standard_count = count_standard_units(block);
-            let meets_duration = block.duration_sec >= MIN_BLOCK_DURATION_SEC;
-            let meets_standard_units = standard_count >= MIN_STANDARD_UNITS;
-
-            meets_duration && meets_standard_units
+            match loudness::summarize(loudness_samples, block.start_ms, block.end_ms) {
+                Some(summary) if loudness::looks_like_program(&summary, max_range_lu) => {
+                    progress!(
+                        verbose,
+                        "Rejected block {}-{}ms: loudness range {:.1} LU looks like program",
+                        block.start_ms, block.end_ms, summary.range_lu
+                    );
+                    false
+                }
+                _ => true,
+            }
         })
         .collect()
 }
@@ -648,7 +2520,135 @@ mod tests {
             SilenceSegment { start_ms: 1900, end_ms: 2100, duration_ms: 200 },
             SilenceSegment { start_ms: 9000, end_ms: 9050, duration_ms: 50 },
         ];
-        assert_eq!(detect_start_offset_ms(&segments), Some(2000));
+        assert_eq!(detect_start_offset_ms(&segments, DetectorConfig::default().start_offset_window), Some(2000));
+    }
+
+    #[test]
+    fn test_detect_start_offset() {
+        let silence = |center_ms: i64| SilenceSegment {
+            start_ms: center_ms - 250,
+            end_ms: center_ms + 250,
+            duration_ms: 500,
+        };
+        let segments = vec![silence(3000), silence(18000), silence(33000), silence(48000), silence(63000)];
+        let config = DetectorConfig::default();
+        let start = detect_start_offset(&segments, &[], &config).unwrap();
+        assert_eq!((start.offset_ms, start.reason), (3000, StartOffsetReason::FirstSilence));
+
+        // CMの途中から録画が始まった場合は先頭のCMブロックの終了位置
+        let blocks = vec![CmBlock::new(3000, 63000, Vec::new())];
+        let start = detect_start_offset(&segments, &blocks, &config).unwrap();
+        assert_eq!((start.offset_ms, start.reason), (63000, StartOffsetReason::LeadingCmBlock));
+
+        // 探索範囲を広げると余白の長い録画にも対応できる
+        let late = vec![silence(20000), silence(300000)];
+        assert!(detect_start_offset(&late, &[], &config).is_none());
+        let wide = DetectorConfig { start_offset_window: Range::new(2000, 30000), ..DetectorConfig::default() };
+        assert_eq!(detect_start_offset(&late, &[], &wide).unwrap().offset_ms, 20000);
+
+        // 予定開始時刻: 21:00:00 の録画で 21:00:18 開始予定 → 18000 付近の無音区間
+        let scheduled = DetectorConfig {
+            recording_start: Timestamp::parse_rfc3339("2024-04-01T21:00:00+09:00"),
+            scheduled_start: Timestamp::parse_rfc3339("2024-04-01T21:00:17+09:00"),
+            ..DetectorConfig::default()
+        };
+        let start = detect_start_offset(&segments, &[], &scheduled).unwrap();
+        assert_eq!((start.offset_ms, start.reason), (18000, StartOffsetReason::ScheduledSilence));
+        let start = detect_start_offset(&[], &[], &scheduled).unwrap();
+        assert_eq!((start.offset_ms, start.reason), (17000, StartOffsetReason::Scheduled));
+    }
+
+    #[test]
+    fn test_mark_padding_blocks() {
+        let config = DetectorConfig {
+            scheduled_start_ms: Some(30000),
+            scheduled_end_ms: Some(1_830_000),
+            ..DetectorConfig::default()
+        };
+        let mut blocks = vec![
+            CmBlock::new(0, 30000, Vec::new()),
+            CmBlock::new(600000, 690000, Vec::new()),
+            CmBlock::new(1_830_000, 1_860_000, Vec::new()),
+        ];
+        blocks[1].confidence = Some(0.8);
+        mark_padding_blocks(&mut blocks, &config);
+        let padding: Vec<Option<Padding>> = blocks.iter().map(|b| b.padding).collect();
+        assert_eq!(padding, vec![Some(Padding::BeforeStart), None, Some(Padding::AfterEnd)]);
+        assert_eq!(blocks[0].padding_weight, Some(0.5));
+        assert_eq!(blocks[1].padding_weight, None);
+        // confidence（黒画面の一致率）は変えない
+        assert_eq!(blocks[0].confidence, None);
+        assert_eq!(blocks[1].confidence, Some(0.8));
+
+        // 予定開始時刻（ファイル先頭からの位置）は本編開始位置の推定にも使われる
+        let segments = vec![SilenceSegment { start_ms: 29800, end_ms: 30300, duration_ms: 500 }];
+        let start = detect_start_offset(&segments, &[], &config).unwrap();
+        assert_eq!((start.offset_ms, start.reason), (30050, StartOffsetReason::ScheduledSilence));
+    }
+
+    #[test]
+    fn test_detect_end_offset() {
+        let silence = |center_ms: i64| SilenceSegment {
+            start_ms: center_ms - 250,
+            end_ms: center_ms + 250,
+            duration_ms: 500,
+        };
+        let segments = vec![silence(3000), silence(1_770_000), silence(1_785_000), silence(1_800_000)];
+        let config = DetectorConfig::default();
+        let end = detect_end_offset(&segments, &[], Some(3000), Some(1_830_000), &config).unwrap();
+        assert_eq!((end.offset_ms, end.reason), (1_830_000, EndOffsetReason::Duration));
+        assert!(detect_end_offset(&segments, &[], Some(3000), None, &config).is_none());
+
+        // 末尾のCMブロックの手前で本編が終わる（その後の予告・余白は本編に含めない）
+        let blocks = vec![CmBlock::new(1_770_000, 1_810_000, Vec::new())];
+        let end = detect_end_offset(&segments, &blocks, Some(3000), Some(1_830_000), &config).unwrap();
+        assert_eq!((end.offset_ms, end.reason), (1_770_000, EndOffsetReason::TrailingCmBlock));
+        let programs = compute_program_segments(&blocks, Some(3000), Some(end.offset_ms));
+        assert_eq!(programs.len(), 1);
+        assert_eq!((programs[0].start_ms, programs[0].end_ms), (3000, 1_770_000));
+
+        // 番組の長さ（本編開始位置から29分45秒）
+        let hinted = DetectorConfig { program_duration_ms: Some(1_785_000), ..DetectorConfig::default() };
+        let end = detect_end_offset(&segments, &[], Some(3000), Some(1_830_000), &hinted).unwrap();
+        assert_eq!((end.offset_ms, end.reason), (1_785_000, EndOffsetReason::ScheduledSilence));
+
+        // 近くに無音区間がない予定終了時刻はそのまま使う
+        let scheduled = DetectorConfig {
+            recording_start: Timestamp::parse_rfc3339("2024-04-01T21:00:00+09:00"),
+            scheduled_end: Timestamp::parse_rfc3339("2024-04-01T21:29:00+09:00"),
+            ..DetectorConfig::default()
+        };
+        let end = detect_end_offset(&segments, &blocks, Some(3000), Some(1_830_000), &scheduled).unwrap();
+        assert_eq!((end.offset_ms, end.reason), (1_740_000, EndOffsetReason::Scheduled));
+        let programs = compute_program_segments(&blocks, Some(3000), Some(end.offset_ms));
+        assert_eq!(programs.len(), 1);
+        assert_eq!(programs[0].end_ms, 1_740_000);
+    }
+
+    #[test]
+    fn test_parse_args_program_offsets() {
+        let args = |v: &[&str]| parse_args(v.iter().map(|s| s.to_string()));
+
+        let window = args(&["--start-window", "2-30"]).unwrap().config.start_offset_window;
+        assert_eq!((window.start, window.end), (2000, 30000));
+        assert!(args(&["--start-window", "30-2"]).is_err());
+        assert!(args(&["--scheduled-start", "2024-04-01T21:00:17+09:00"]).unwrap().config.scheduled_start.is_some());
+        assert!(args(&["--scheduled-end", "2024-04-01T21:30:00+09:00"]).unwrap().config.scheduled_end.is_some());
+        assert_eq!(args(&["--program-duration", "1770"]).unwrap().config.program_duration_ms, Some(1_770_000));
+        assert_eq!(args(&["--end-window", "60"]).unwrap().config.end_offset_window_ms, 60000);
+        for value in ["-1", "NaN", "inf"] {
+            assert!(args(&["--program-duration", value]).is_err());
+            assert!(args(&["--end-window", value]).is_err());
+        }
+    }
+
+    #[test]
+    fn test_parse_args_breaks() {
+        let args = |v: &[&str]| parse_args(v.iter().map(|s| s.to_string()));
+
+        assert_eq!(args(&["--breaks", "3"]).unwrap().config.expected_breaks, Some(3));
+        assert_eq!(args(&["--break-positions", "10,50,95"]).unwrap().config.break_positions, vec![0.1, 0.5, 0.95]);
+        assert!(args(&["--break-positions", "10,x"]).is_err());
     }
 
     #[test]
@@ -746,7 +2746,7 @@ mod tests {
             },
         ];
 
-        let blocks = detect_blocks_range_based(&segments);
+        let blocks = detect_blocks_range_based(&segments, &DetectorConfig::default());
 
         // 範囲ベースアルゴリズムでは、チェーンが途切れずに検出されるべき
         assert!(!blocks.is_empty(), "Should detect at least one CM block");
@@ -768,7 +2768,7 @@ mod tests {
             SilenceSegment { start_ms: 74500, end_ms: 75500, duration_ms: 1000 },
         ];
 
-        let blocks = detect_blocks_range_based(&segments);
+        let blocks = detect_blocks_range_based(&segments, &DetectorConfig::default());
         assert_eq!(blocks.len(), 1, "Should detect exactly one CM block");
 
         let block = &blocks[0];
@@ -795,7 +2795,7 @@ mod tests {
             SilenceSegment { start_ms: 73000, end_ms: 77000, duration_ms: 4000 },
         ];
 
-        let blocks = detect_blocks_range_based(&segments);
+        let blocks = detect_blocks_range_based(&segments, &DetectorConfig::default());
         assert_eq!(blocks.len(), 1);
 
         let block = &blocks[0];
@@ -828,7 +2828,7 @@ mod tests {
             SilenceSegment { start_ms: 155000, end_ms: 155100, duration_ms: 100 },
         ];
 
-        let blocks = detect_blocks_range_based(&segments);
+        let blocks = detect_blocks_range_based(&segments, &DetectorConfig::default());
 
         // 短時間単位がチェーンを継続するので、1つのブロックとして検出される
         assert_eq!(blocks.len(), 1, "Short unit should continue chain, resulting in one block");
@@ -838,19 +2838,23 @@ mod tests {
         assert_eq!(block.segments.len(), 11, "Block should have 11 segments");
 
         // フィルタ後も残る（標準単位 >= 2、時間 >= 60s）
-        let filtered = filter_blocks_by_standard_units(vec![block.clone()]);
+        let filtered = filter_blocks_by_standard_units(vec![block.clone()], &DetectorConfig::default());
         assert_eq!(filtered.len(), 1, "Block should pass standard unit filter");
     }
 
     #[test]
-    fn test_is_short_unit() {
-        assert!(is_short_unit(5.0));
-        assert!(is_short_unit(5.3));
-        assert!(is_short_unit(4.7));
-        assert!(is_short_unit(10.0));
-        assert!(is_short_unit(10.4));
-        assert!(!is_short_unit(7.0));
-        assert!(!is_short_unit(15.0));
+    fn test_match_short_unit() {
+        let config = DetectorConfig::default();
+        let is_short_unit = |gap_ms: i64| {
+            match_short_unit(GapMetric::Center, &Range::new(0, 0), &Range::new(gap_ms, gap_ms), &config).is_some()
+        };
+        assert!(is_short_unit(5000));
+        assert!(is_short_unit(5300));
+        assert!(is_short_unit(4700));
+        assert!(is_short_unit(10000));
+        assert!(is_short_unit(10400));
+        assert!(!is_short_unit(7000));
+        assert!(!is_short_unit(15000));
     }
 
     #[test]
@@ -864,10 +2868,10 @@ mod tests {
             SilenceSegment { start_ms: 120000, end_ms: 121000, duration_ms: 1000 }, // 50s gap
         ];
 
-        let blocks = detect_blocks_range_based(&segments);
+        let blocks = detect_blocks_range_based(&segments, &DetectorConfig::default());
         // 検出段階では一部のブロックが生成される可能性がある
         // しかし最終フィルタで標準単位数・時間条件を満たさないものは除外される
-        let filtered = filter_blocks_by_standard_units(blocks);
+        let filtered = filter_blocks_by_standard_units(blocks, &DetectorConfig::default());
         assert!(filtered.is_empty(), "Should not have valid CM blocks after filter");
     }
 
@@ -893,7 +2897,7 @@ mod tests {
             SilenceSegment { start_ms: 239500, end_ms: 240500, duration_ms: 1000 },
         ];
 
-        let blocks = detect_blocks_range_based(&segments);
+        let blocks = detect_blocks_range_based(&segments, &DetectorConfig::default());
 
         // 90sギャップでチェーンが切断されるので、2つの別々のブロックになるべき
         assert_eq!(blocks.len(), 2, "90s gap should break chain into two blocks");
@@ -935,7 +2939,7 @@ mod tests {
             SilenceSegment { start_ms: 120000, end_ms: 121000, duration_ms: 1000 },
         ];
 
-        let blocks = detect_blocks_range_based(&segments);
+        let blocks = detect_blocks_range_based(&segments, &DetectorConfig::default());
         assert_eq!(blocks.len(), 1, "Should detect one CM block before extension");
 
         // 境界拡張前のブロック（中心点ベース）
@@ -945,7 +2949,7 @@ mod tests {
         assert_eq!(block_before.end_ms, 81500, "Before extension: ends at center of [81000,82000]");
 
         // 境界拡張を適用
-        let extended = extend_block_boundaries_with_short_units(&blocks, &segments);
+        let extended = extend_block_boundaries_with_short_units(&blocks, &segments, &DetectorConfig::default());
         assert_eq!(extended.len(), 1, "Should still have one CM block after extension");
 
         let block = &extended[0];
@@ -991,7 +2995,7 @@ mod tests {
             SilenceSegment { start_ms: 95000, end_ms: 95100, duration_ms: 100 },
         ];
 
-        let blocks = detect_blocks_range_based(&segments);
+        let blocks = detect_blocks_range_based(&segments, &DetectorConfig::default());
         assert_eq!(blocks.len(), 1, "Should detect one CM block");
 
         let block = &blocks[0];
@@ -1041,8 +3045,8 @@ mod tests {
             SilenceSegment { start_ms: 100000, end_ms: 101000, duration_ms: 1000 },
         ];
 
-        let blocks = detect_blocks_range_based(&segments);
-        let extended = extend_block_boundaries_with_short_units(&blocks, &segments);
+        let blocks = detect_blocks_range_based(&segments, &DetectorConfig::default());
+        let extended = extend_block_boundaries_with_short_units(&blocks, &segments, &DetectorConfig::default());
 
         assert_eq!(extended.len(), 1);
         let block = &extended[0];
@@ -1065,4 +3069,816 @@ mod tests {
         let std_count = count_standard_units(block);
         assert_eq!(std_count, 3, "Should count 3 standard units");
     }
+
+    #[test]
+    fn test_gap_metric_values() {
+        let prev = Range::new(0, 1000);
+        let curr = Range::new(6000, 7000);
+        // 中心点間: 500 → 6500
+        assert_eq!(GapMetric::Center.gap_ms(&prev, &curr), 6000);
+        // 端点間: 1000 → 6000
+        assert_eq!(GapMetric::Edge.gap_ms(&prev, &curr), 5000);
+        assert_eq!(GapMetric::RangeIntersection.gap_ms(&prev, &curr), 6000);
+
+        // 5s ± 0.5s: 中心点間(6s)は不一致、端点間(5s)と範囲交差は一致
+        assert!(GapMetric::Center.accepts(&prev, &curr, 5000, TOLERANCE_MS).is_none());
+        assert!(GapMetric::Edge.accepts(&prev, &curr, 5000, TOLERANCE_MS).is_some());
+        let range = GapMetric::RangeIntersection
+            .accepts(&prev, &curr, 5000, TOLERANCE_MS)
+            .unwrap();
+        // 期待範囲 [4500, 6500] と [6000, 7000] の交差
+        assert_eq!((range.start, range.end), (6000, 6500));
+
+        assert_eq!(GapMetric::Center.segment_bounds(&prev, &curr), (500, 6500));
+        assert_eq!(GapMetric::Edge.segment_bounds(&prev, &curr), (1000, 6000));
+    }
+
+    #[test]
+    fn test_uniform_metric_passes_agree() {
+        // 中心点間6s / 端点間5s の境界的な間隔を、チェーン検出・ブロック間統合・境界拡張の
+        // 全パスが同じように判定することを確認
+        let segments = vec![
+            SilenceSegment { start_ms: 0, end_ms: 1000, duration_ms: 1000 },
+            SilenceSegment { start_ms: 6000, end_ms: 7000, duration_ms: 1000 },
+        ];
+        let prev = segments[0].range();
+        let curr = segments[1].range();
+        let config = DetectorConfig::default();
+
+        for (metric, expected) in [
+            (GapMetric::Center, false),
+            (GapMetric::Edge, true),
+            (GapMetric::RangeIntersection, true),
+        ] {
+            let metrics = GapMetrics::uniform(metric);
+            let chain = matches!(
+                classify_gap(&prev, &curr, metrics.chain_standard, metrics.chain_short, &config),
+                Some(UnitMatch::Short(_))
+            );
+            let merge = chain_short_units_in_gap(&segments, &prev, &curr, metrics.merge, &config).is_some();
+            let extension = match_short_unit(metrics.extension, &prev, &curr, &config).is_some();
+            assert_eq!(chain, expected, "chain detection with {:?}", metric);
+            assert_eq!(merge, expected, "between-block merge with {:?}", metric);
+            assert_eq!(extension, expected, "boundary extension with {:?}", metric);
+        }
+
+        // 互換プリセットではパスごとに判定が異なる（チェーン: 不一致、拡張: 一致）
+        let legacy = GapMetrics::legacy();
+        assert!(classify_gap(&prev, &curr, legacy.chain_standard, legacy.chain_short, &config).is_none());
+        assert!(match_short_unit(legacy.extension, &prev, &curr, &config).is_some());
+    }
+
+    #[test]
+    fn test_uniform_metric_pipeline() {
+        // program → 5s → [75s CM block] → 5s → program
+        // 無音区間は1s、端点間がちょうど 5s / 15s（中心点間は 6s / 16s）
+        let segments = vec![
+            SilenceSegment { start_ms: 0, end_ms: 1000, duration_ms: 1000 },
+            SilenceSegment { start_ms: 6000, end_ms: 7000, duration_ms: 1000 },
+            SilenceSegment { start_ms: 22000, end_ms: 23000, duration_ms: 1000 },
+            SilenceSegment { start_ms: 38000, end_ms: 39000, duration_ms: 1000 },
+            SilenceSegment { start_ms: 54000, end_ms: 55000, duration_ms: 1000 },
+            SilenceSegment { start_ms: 70000, end_ms: 71000, duration_ms: 1000 },
+            SilenceSegment { start_ms: 86000, end_ms: 87000, duration_ms: 1000 },
+            SilenceSegment { start_ms: 92000, end_ms: 93000, duration_ms: 1000 },
+            SilenceSegment { start_ms: 130000, end_ms: 131000, duration_ms: 1000 },
+        ];
+
+        let run = |config: &DetectorConfig| {
+            let blocks = detect_blocks_range_based(&segments, config);
+            let blocks = merge_blocks_with_short_units(&blocks, &segments, config);
+            extend_block_boundaries_with_short_units(&blocks, &segments, config)
+        };
+
+        // 端点間: チェーン検出の段階で両端の5s単位が含まれ、境界拡張では何も追加されない
+        let config = DetectorConfig {
+            gap_metrics: GapMetrics::uniform(GapMetric::Edge),
+            ..DetectorConfig::default()
+        };
+        let detected = detect_blocks_range_based(&segments, &config);
+        assert_eq!(detected.len(), 1);
+        assert_eq!((detected[0].start_ms, detected[0].end_ms), (500, 92500));
+        let blocks = run(&config);
+        assert_eq!((blocks[0].start_ms, blocks[0].end_ms), (500, 92500));
+        assert_eq!(blocks[0].segments.len(), 7);
+        assert_eq!(count_standard_units(&blocks[0]), 5);
+        // セグメント境界も端点間
+        assert_eq!((blocks[0].segments[0].start_ms, blocks[0].segments[0].end_ms), (1000, 6000));
+
+        // 中心点間: 16s は標準単位、6s は短時間単位として扱われない
+        let config = DetectorConfig {
+            gap_metrics: GapMetrics::uniform(GapMetric::Center),
+            ..DetectorConfig::default()
+        };
+        assert!(run(&config).is_empty());
+    }
+
+    #[test]
+    fn test_legacy_short_unit_carries_intersection() {
+        // 長い無音区間: A → 5s → B → 30s → C → 15s × 5
+        // 短時間単位は中心点間で判定し、次の判定には期待範囲との交差 [104500, 108500] を引き継ぐ
+        // （B 全体を引き継ぐと C が30秒の範囲に入り、A からのブロックになる）
+        let mut segments = vec![
+            SilenceSegment { start_ms: 2750, end_ms: 3250, duration_ms: 500 },
+            SilenceSegment { start_ms: 100000, end_ms: 103000, duration_ms: 3000 },
+            SilenceSegment { start_ms: 103500, end_ms: 109500, duration_ms: 6000 },
+            SilenceSegment { start_ms: 132000, end_ms: 133800, duration_ms: 1800 },
+        ];
+        for i in 1..=5 {
+            let center = 132900 + i * 15000;
+            segments.push(SilenceSegment { start_ms: center - 500, end_ms: center + 500, duration_ms: 1000 });
+        }
+        segments.push(SilenceSegment { start_ms: 407400, end_ms: 407900, duration_ms: 500 });
+
+        // 従来の実装の出力
+        let config = DetectorConfig::default();
+        let blocks = filter_blocks_by_standard_units(detect_blocks(&segments, &config, false), &config);
+        assert_eq!(blocks.len(), 1);
+        assert_eq!((blocks[0].start_ms, blocks[0].end_ms), (132900, 207900));
+        assert_eq!(count_standard_units(&blocks[0]), 5);
+    }
+
+    #[test]
+    fn test_parse_args_gap_metric() {
+        let args = |v: &[&str]| parse_args(v.iter().map(|s| s.to_string()));
+
+        assert_eq!(args(&[]).unwrap().config.gap_metrics, GapMetrics::legacy());
+        assert_eq!(
+            args(&["--gap-metric", "edge"]).unwrap().config.gap_metrics,
+            GapMetrics::uniform(GapMetric::Edge)
+        );
+        assert_eq!(
+            args(&["--gap-metric", "range"]).unwrap().config.gap_metrics,
+            GapMetrics::uniform(GapMetric::RangeIntersection)
+        );
+        assert!(args(&["--gap-metric", "foo"]).is_err());
+        assert!(args(&["--gap-metric"]).is_err());
+        assert!(args(&["--unknown"]).is_err());
+    }
+
+    fn block(start_ms: i64, end_ms: i64) -> CmBlock {
+        CmBlock::new(start_ms, end_ms, Vec::new())
+    }
+
+    #[test]
+    fn test_merge_chains_through_interior_silences() {
+        // [block A] → 5s → 10s → [block B]
+        // ギャップ内の無音区間を通る短時間単位チェーンが作れるので統合される
+        let segments = vec![
+            SilenceSegment { start_ms: 0, end_ms: 1000, duration_ms: 1000 },
+            SilenceSegment { start_ms: 60000, end_ms: 61000, duration_ms: 1000 },
+            SilenceSegment { start_ms: 65000, end_ms: 66000, duration_ms: 1000 },
+            SilenceSegment { start_ms: 75000, end_ms: 76000, duration_ms: 1000 },
+            SilenceSegment { start_ms: 135000, end_ms: 136000, duration_ms: 1000 },
+        ];
+        let blocks = vec![block(500, 60500), block(75500, 135500)];
+
+        let merged = merge_blocks_with_short_units(&blocks, &segments, &DetectorConfig::default());
+        assert_eq!(merged.len(), 1, "Short unit chain in the gap should merge blocks");
+        assert_eq!((merged[0].start_ms, merged[0].end_ms), (500, 135500));
+
+        // ギャップ内のサブセグメント（中心点間）が記録される
+        let gap_segments = &merged[0].segments;
+        assert_eq!(gap_segments.len(), 2);
+        assert_eq!((gap_segments[0].start_ms, gap_segments[0].end_ms), (60500, 65500));
+        assert_eq!((gap_segments[1].start_ms, gap_segments[1].end_ms), (65500, 75500));
+        assert!(gap_segments.iter().all(|seg| !seg.is_standard));
+    }
+
+    #[test]
+    fn test_merge_rejects_gap_with_irregular_interior_silence() {
+        // [block A] → 20s の本編（13s地点に無音区間）→ [block B]
+        // 合計は10sの倍数だが、13s / 7s は短時間単位ではないので統合しない
+        let segments = vec![
+            SilenceSegment { start_ms: 0, end_ms: 1000, duration_ms: 1000 },
+            SilenceSegment { start_ms: 60000, end_ms: 61000, duration_ms: 1000 },
+            SilenceSegment { start_ms: 73000, end_ms: 74000, duration_ms: 1000 },
+            SilenceSegment { start_ms: 80000, end_ms: 81000, duration_ms: 1000 },
+            SilenceSegment { start_ms: 140000, end_ms: 141000, duration_ms: 1000 },
+        ];
+        let blocks = vec![block(500, 60500), block(80500, 140500)];
+
+        let merged = merge_blocks_with_short_units(&blocks, &segments, &DetectorConfig::default());
+        assert_eq!(merged.len(), 2, "Irregular interior silence should prevent merge");
+    }
+
+    fn sponsor_config(policy: SponsorPolicy) -> DetectorConfig {
+        DetectorConfig { sponsor_policy: policy, ..DetectorConfig::default() }
+    }
+
+    #[test]
+    fn test_sponsor_absorbed_edge_segments() {
+        // program → 5s(提供) → [75s CM block] → 5s(提供) → program
+        // 両端の5s単位は境界拡張で取り込まれ、提供クレジットとして分類される
+        let segments = vec![
+            SilenceSegment { start_ms: 0, end_ms: 1000, duration_ms: 1000 },
+            SilenceSegment { start_ms: 6000, end_ms: 7000, duration_ms: 1000 },
+            SilenceSegment { start_ms: 21000, end_ms: 22000, duration_ms: 1000 },
+            SilenceSegment { start_ms: 36000, end_ms: 37000, duration_ms: 1000 },
+            SilenceSegment { start_ms: 51000, end_ms: 52000, duration_ms: 1000 },
+            SilenceSegment { start_ms: 66000, end_ms: 67000, duration_ms: 1000 },
+            SilenceSegment { start_ms: 81000, end_ms: 82000, duration_ms: 1000 },
+            SilenceSegment { start_ms: 87000, end_ms: 88000, duration_ms: 1000 },
+            SilenceSegment { start_ms: 120000, end_ms: 121000, duration_ms: 1000 },
+        ];
+        let config = DetectorConfig::default();
+        let blocks = detect_blocks_range_based(&segments, &config);
+        let blocks = extend_block_boundaries_with_short_units(&blocks, &segments, &config);
+
+        // 無音区間だけでは5秒のCMと区別できないため、端のセグメントはCMのまま
+        let silence_only = fuse_boundaries(&segments, &[], &[], &[], &[], &config);
+        let (cm, sponsors) =
+            classify_sponsor_segments(blocks.clone(), &silence_only, &sponsor_config(SponsorPolicy::Cm));
+        assert!(sponsors.is_empty());
+        assert_eq!((cm[0].start_ms, cm[0].end_ms), (500, 87500));
+        assert!(cm[0].segments.iter().all(|seg| seg.kind == SegmentKind::Cm));
+        let (program, _) =
+            classify_sponsor_segments(blocks.clone(), &silence_only, &sponsor_config(SponsorPolicy::Program));
+        assert_eq!((program[0].start_ms, program[0].end_ms), (500, 87500));
+
+        // --sponsor-edge duration: 長さだけで提供クレジットとする
+        let by_duration = DetectorConfig { sponsor_edge_by_duration: true, ..sponsor_config(SponsorPolicy::Cm) };
+        let (cm, _) = classify_sponsor_segments(blocks.clone(), &silence_only, &by_duration);
+        assert_eq!(cm[0].segments[0].kind, SegmentKind::Sponsor);
+        assert_eq!(cm[0].segments[6].kind, SegmentKind::Sponsor);
+
+        // 提供クレジットとCMの間の境界に黒画面がある
+        let black_segments =
+            vec![BlackSegment { start_ms: 6200, end_ms: 6800 }, BlackSegment { start_ms: 81200, end_ms: 81800 }];
+        let boundaries = fuse_boundaries(&segments, &black_segments, &[], &[], &[], &config);
+
+        // CMと一緒にカット: ブロック範囲はそのまま、端のセグメントが sponsor になる
+        let (cm, sponsors) =
+            classify_sponsor_segments(blocks.clone(), &boundaries, &sponsor_config(SponsorPolicy::Cm));
+        assert!(sponsors.is_empty());
+        assert_eq!((cm[0].start_ms, cm[0].end_ms), (500, 87500));
+        assert_eq!(cm[0].segments.len(), 7);
+        assert_eq!(cm[0].segments[0].kind, SegmentKind::Sponsor);
+        assert_eq!(cm[0].segments[6].kind, SegmentKind::Sponsor);
+        assert!(cm[0].segments[1..6].iter().all(|seg| seg.kind == SegmentKind::Cm));
+
+        // 本編側に残す: 提供クレジットを除いた範囲になる
+        let (program, sponsors) = classify_sponsor_segments(
+            blocks.clone(),
+            &boundaries,
+            &sponsor_config(SponsorPolicy::Program),
+        );
+        assert!(sponsors.is_empty());
+        assert_eq!((program[0].start_ms, program[0].end_ms), (6500, 81500));
+        assert_eq!(program[0].segments.len(), 5);
+        assert!((program[0].duration_sec - 75.0).abs() < 0.01);
+
+        // 除外後のブロックが最終フィルタを満たさなくなった場合はブロックごと除外する
+        let strict = DetectorConfig { min_block_duration_sec: 80.0, ..sponsor_config(SponsorPolicy::Separate) };
+        let (separate, sponsors) = classify_sponsor_segments(blocks.clone(), &boundaries, &strict);
+        assert!(separate.is_empty());
+        assert!(sponsors.is_empty());
+        // 番組構成のモデルで救済されたブロックは残す
+        let mut rescued = blocks.clone();
+        rescued[0].structure = Some(BreakFit::Rescued);
+        let (separate, _) = classify_sponsor_segments(rescued, &boundaries, &strict);
+        assert_eq!((separate[0].start_ms, separate[0].end_ms), (6500, 81500));
+
+        // 別に出力: ブロックは本編側と同じ、提供クレジットは sponsor_segments へ
+        let (separate, sponsors) =
+            classify_sponsor_segments(blocks, &boundaries, &sponsor_config(SponsorPolicy::Separate));
+        assert_eq!((separate[0].start_ms, separate[0].end_ms), (6500, 81500));
+        assert_eq!(sponsors.len(), 2);
+        assert!(sponsors.iter().all(|seg| seg.kind == SegmentKind::Sponsor));
+        assert_eq!((sponsors[0].start_ms, sponsors[0].end_ms), (500, 6500));
+        assert_eq!((sponsors[1].start_ms, sponsors[1].end_ms), (81500, 87500));
+    }
+
+    #[test]
+    fn test_sponsor_trimmed_block_emptied() {
+        // 5s x 2 のブロックは両端の提供クレジットを除くと空になり、除外される
+        let segments: Vec<SilenceSegment> = [0, 5000, 10000]
+            .iter()
+            .map(|&t| SilenceSegment { start_ms: t, end_ms: t + 1000, duration_ms: 1000 })
+            .collect();
+        let block = CmBlock::new(
+            500,
+            10500,
+            vec![
+                CmCandidate::new(500, 5500, false, SegmentKind::Cm),
+                CmCandidate::new(5500, 10500, false, SegmentKind::Cm),
+            ],
+        );
+        let config = DetectorConfig { sponsor_edge_by_duration: true, ..sponsor_config(SponsorPolicy::Program) };
+        let boundaries = fuse_boundaries(&segments, &[], &[], &[], &[], &config);
+        let (blocks, sponsors) = classify_sponsor_segments(vec![block], &boundaries, &config);
+        assert!(blocks.is_empty());
+        assert!(sponsors.is_empty());
+    }
+
+    #[test]
+    fn test_sponsor_adjacent_segment_not_absorbed() {
+        // program → 7s(提供、短時間単位ではない) → [75s CM block] → program
+        let segments = vec![
+            SilenceSegment { start_ms: 0, end_ms: 1000, duration_ms: 1000 },
+            SilenceSegment { start_ms: 7500, end_ms: 8500, duration_ms: 1000 },
+            SilenceSegment { start_ms: 22500, end_ms: 23500, duration_ms: 1000 },
+            SilenceSegment { start_ms: 37500, end_ms: 38500, duration_ms: 1000 },
+            SilenceSegment { start_ms: 52500, end_ms: 53500, duration_ms: 1000 },
+            SilenceSegment { start_ms: 67500, end_ms: 68500, duration_ms: 1000 },
+            SilenceSegment { start_ms: 82500, end_ms: 83500, duration_ms: 1000 },
+            SilenceSegment { start_ms: 150000, end_ms: 151000, duration_ms: 1000 },
+        ];
+        let config = DetectorConfig::default();
+        let blocks = detect_blocks_range_based(&segments, &config);
+        let blocks = extend_block_boundaries_with_short_units(&blocks, &segments, &config);
+        assert_eq!((blocks[0].start_ms, blocks[0].end_ms), (8000, 83000));
+        let boundaries = fuse_boundaries(&segments, &[], &[], &[], &[], &config);
+
+        // デフォルト: ブロックの範囲は変えず、sponsor_segments に出力するのみ
+        let (cm, sponsors) =
+            classify_sponsor_segments(blocks.clone(), &boundaries, &sponsor_config(SponsorPolicy::Cm));
+        assert_eq!((cm[0].start_ms, cm[0].end_ms), (8000, 83000));
+        assert_eq!(cm[0].segments.len(), 5);
+        assert_eq!(sponsors.len(), 1);
+        assert_eq!((sponsors[0].start_ms, sponsors[0].end_ms), (500, 8000));
+
+        // --sponsor-adjacent absorb: ブロックに含める
+        let absorb = DetectorConfig { absorb_adjacent_sponsors: true, ..sponsor_config(SponsorPolicy::Cm) };
+        let (cm, _) = classify_sponsor_segments(blocks.clone(), &boundaries, &absorb);
+        assert_eq!((cm[0].start_ms, cm[0].end_ms), (500, 83000));
+        assert_eq!(cm[0].segments[0].kind, SegmentKind::Sponsor);
+        assert_eq!((cm[0].segments[0].start_ms, cm[0].segments[0].end_ms), (500, 8000));
+
+        let (separate, sponsors) =
+            classify_sponsor_segments(blocks, &boundaries, &sponsor_config(SponsorPolicy::Separate));
+        assert_eq!((separate[0].start_ms, separate[0].end_ms), (8000, 83000));
+        assert_eq!(sponsors.len(), 1);
+    }
+
+    /// 中心 times_sec の0.5秒の無音区間を出力した silencedetect のログ
+    fn silencedetect_log(times_sec: &[f64]) -> String {
+        let mut log = String::from("  Duration: 00:30:00.00, start: 0.000000, bitrate: 1000 kb/s\n");
+        for t in times_sec {
+            log.push_str(&format!("[silencedetect @ 0x1] silence_start: {:.3}\n", t - 0.25));
+            log.push_str(&format!("[silencedetect @ 0x1] silence_end: {:.3} | silence_duration: 0.500\n", t + 0.25));
+        }
+        log
+    }
+
+    /// デフォルト設定での無音区間のみのログからの検出（本編開始位置とCMブロック）
+    fn detect_default(log: &str) -> (Option<i64>, Vec<CmBlock>) {
+        let detection = detect_from_parsed(parser::parse_ffmpeg_log(log), &CliArgs::default(), false).unwrap();
+        (detection.start_offset.map(|start| start.offset_ms), detection.blocks)
+    }
+
+    #[test]
+    fn test_default_output_matches_baseline() {
+        // 提供クレジットらしい7〜8秒の本編がCMブロックの前後に隣接するログ
+        // デフォルトの出力は従来（baseline）と同じで、隣接区間はブロックに含めない
+        let mut times = vec![3.0, 293.0];
+        times.extend((0..5).map(|i| 300.0 + i as f64 * 15.0));
+        times.push(367.0);
+        times.extend((0..7).map(|i| 900.0 + i as f64 * 15.0));
+        times.push(998.0);
+        let (start, blocks) = detect_default(&silencedetect_log(&times));
+
+        assert_eq!(start, Some(3000));
+        let bounds: Vec<(i64, i64, usize)> =
+            blocks.iter().map(|b| (b.start_ms, b.end_ms, b.segments.len())).collect();
+        assert_eq!(bounds, vec![(300000, 360000, 4), (900000, 990000, 6)]);
+    }
+
+    #[test]
+    fn test_parse_args_sponsor_policy() {
+        let args = |v: &[&str]| parse_args(v.iter().map(|s| s.to_string()));
+
+        assert_eq!(args(&[]).unwrap().config.sponsor_policy, SponsorPolicy::Cm);
+        assert_eq!(
+            args(&["--sponsor-policy", "separate"]).unwrap().config.sponsor_policy,
+            SponsorPolicy::Separate
+        );
+        assert!(args(&["--sponsor-policy", "foo"]).is_err());
+        assert!(!args(&[]).unwrap().config.absorb_adjacent_sponsors);
+        assert!(args(&["--sponsor-adjacent", "absorb"]).unwrap().config.absorb_adjacent_sponsors);
+        assert!(args(&["--sponsor-adjacent", "yes"]).is_err());
+        assert!(!args(&[]).unwrap().config.sponsor_edge_by_duration);
+        assert!(args(&["--sponsor-edge", "duration"]).unwrap().config.sponsor_edge_by_duration);
+        assert!(args(&["--sponsor-edge", "length"]).is_err());
+    }
+
+    /// 15s x 2 → 20s（ステーションID）→ 15s x 2 の無音区間列（開始 offset_ms）
+    fn station_id_segments(offset_ms: i64) -> Vec<SilenceSegment> {
+        [0, 15000, 30000, 50000, 65000, 80000]
+            .iter()
+            .map(|t| SilenceSegment {
+                start_ms: offset_ms + t,
+                end_ms: offset_ms + t + 100,
+                duration_ms: 100,
+            })
+            .collect()
+    }
+
+    fn station_id_config() -> DetectorConfig {
+        DetectorConfig {
+            bridges: vec![BridgeType::parse("station_id=20").unwrap()],
+            ..DetectorConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_bridge_inside_block() {
+        let segments = station_id_segments(0);
+
+        // ブリッジ未設定: 20sでチェーンが切れて2つのブロックになる
+        let blocks = detect_blocks_range_based(&segments, &DetectorConfig::default());
+        assert_eq!(blocks.len(), 2);
+
+        // ブリッジ設定あり: 1つのブロックとして検出される
+        let blocks = detect_blocks_range_based(&segments, &station_id_config());
+        assert_eq!(blocks.len(), 1);
+        let block = &blocks[0];
+        assert_eq!(block.segments.len(), 5);
+        assert_eq!(block.segments[2].kind, SegmentKind::Bridge);
+        assert_eq!(block.segments[2].bridge_type.as_deref(), Some("station_id"));
+        assert!(!block.segments[2].is_standard);
+        assert_eq!(count_standard_units(block), 4);
+        assert_eq!(filter_blocks_by_standard_units(blocks, &station_id_config()).len(), 1);
+    }
+
+    #[test]
+    fn test_bridge_at_block_edge() {
+        // [75s CM block] → 20s（ステーションID）→ program
+        let mut segments: Vec<SilenceSegment> = (0..6)
+            .map(|i| SilenceSegment {
+                start_ms: i * 15000,
+                end_ms: i * 15000 + 100,
+                duration_ms: 100,
+            })
+            .collect();
+        segments.push(SilenceSegment { start_ms: 95000, end_ms: 95100, duration_ms: 100 });
+        segments.push(SilenceSegment { start_ms: 200000, end_ms: 200100, duration_ms: 100 });
+
+        let config = station_id_config();
+        let blocks = detect_blocks_range_based(&segments, &config);
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].end_ms, 95050, "Station ID is chained at the block edge");
+
+        // チェーンから外れた場合でも境界拡張で取り込まれる
+        let block = CmBlock {
+            segments: blocks[0].segments[..5].to_vec(),
+            ..block(50, 75050)
+        };
+        let extended = extend_block_boundaries_with_short_units(&[block], &segments, &config);
+        assert_eq!(extended[0].end_ms, 95050);
+        assert_eq!(extended[0].segments.last().unwrap().kind, SegmentKind::Bridge);
+    }
+
+    #[test]
+    fn test_bridge_wall_clock_window() {
+        // 20sのステーションIDは録画開始から 1800s 付近
+        let segments = station_id_segments(1770000);
+        let mut config = station_id_config();
+
+        // 録画開始 21:00:00 → ステーションIDは 21:30 付近なのでブリッジを許可
+        config.recording_start = Timestamp::parse_rfc3339("2024-04-01T21:00:00+09:00");
+        assert_eq!(detect_blocks_range_based(&segments, &config).len(), 1);
+
+        // 録画開始 21:10:00 → ステーションIDは 21:40 付近なのでブリッジを許可しない
+        config.recording_start = Timestamp::parse_rfc3339("2024-04-01T21:10:00+09:00");
+        assert_eq!(detect_blocks_range_based(&segments, &config).len(), 2);
+    }
+
+    #[test]
+    fn test_parse_args_final_filter() {
+        let args = |v: &[&str]| parse_args(v.iter().map(|s| s.to_string()));
+
+        let cli = args(&["--short-units", "5,10", "--min-block-duration", "45.5"]).unwrap();
+        assert_eq!(cli.config.short_units, vec![5.0, 10.0]);
+        assert_eq!(cli.config.min_block_duration_sec, 45.5);
+        assert!(args(&["--short-units", "none"]).unwrap().config.short_units.is_empty());
+
+        for value in ["NaN", "inf", "0", "-5"] {
+            assert!(args(&["--short-units", value]).is_err(), "{}", value);
+        }
+        for value in ["NaN", "inf", "-1"] {
+            assert!(args(&["--min-block-duration", value]).is_err(), "{}", value);
+        }
+    }
+
+    #[test]
+    fn test_parse_args_bridges() {
+        let args = |v: &[&str]| parse_args(v.iter().map(|s| s.to_string()));
+
+        let cli = args(&[
+            "--bridge", "station_id=20", "--bridge", "network_break=40", "--bridge-window", "120",
+            "--recorded-at", "2024-04-01T21:00:00+09:00",
+        ])
+        .unwrap();
+        assert_eq!(cli.config.bridges.len(), 2);
+        assert_eq!(cli.config.bridges[1].name, "network_break");
+        assert_eq!(cli.config.bridges[1].duration_ms, 40000);
+        assert_eq!(cli.config.bridge_window_sec, 120);
+        assert!(cli.config.recording_start.is_some());
+        assert!(!cli.recorded_at_from_log);
+        assert!(args(&["--recorded-at-from-log"]).unwrap().recorded_at_from_log);
+
+        assert!(args(&["--bridge", "station_id"]).is_err());
+        assert!(args(&["--bridge", "=20"]).is_err());
+        assert!(args(&["--bridge-window", "-1"]).is_err());
+        assert!(args(&["--recorded-at", "21:00"]).is_err());
+    }
+
+    #[test]
+    fn test_program_segments_with_wall_clock() {
+        let mut blocks = vec![block(120000, 180000), block(600000, 690000)];
+        let mut programs = compute_program_segments(&blocks, Some(3000), Some(1800000));
+        let bounds: Vec<(i64, i64)> = programs.iter().map(|p| (p.start_ms, p.end_ms)).collect();
+        assert_eq!(bounds, vec![(3000, 120000), (180000, 600000), (690000, 1800000)]);
+
+        // 長さが不明な場合、最後のCM以降の本編は出力しない
+        assert_eq!(compute_program_segments(&blocks, None, None).len(), 2);
+        assert_eq!(compute_program_segments(&blocks, None, None)[0].start_ms, 0);
+
+        let start = Timestamp::parse_rfc3339("2024-04-01T21:00:00+09:00").unwrap();
+        annotate_wall_clock(&mut blocks, &mut programs, start);
+        assert_eq!(blocks[0].start_at.as_deref(), Some("2024-04-01T21:02:00.000+09:00"));
+        assert_eq!(blocks[0].end_at.as_deref(), Some("2024-04-01T21:03:00.000+09:00"));
+        assert_eq!(programs[0].start_at.as_deref(), Some("2024-04-01T21:00:03.000+09:00"));
+        assert_eq!(programs[2].end_at.as_deref(), Some("2024-04-01T21:30:00.000+09:00"));
+    }
+
+    #[test]
+    fn test_cli_overrides_profile() {
+        let path = env::temp_dir().join(format!("cm-detector-profile-{}.json", process::id()));
+        fs::write(&path, r#"{"tolerance_ms": 700, "min_standard_units": 3}"#).unwrap();
+        let args = |v: &[&str]| {
+            let mut args = vec!["--profile".to_string(), path.to_str().unwrap().to_string()];
+            args.extend(v.iter().map(|s| s.to_string()));
+            parse_args(args).and_then(apply_hints_and_profile).unwrap()
+        };
+
+        let cli = args(&[]);
+        assert_eq!((cli.config.tolerance_ms, cli.config.min_standard_units), (700, 3));
+        // コマンドライン引数はプロファイルより優先
+        let cli = args(&["--tolerance", "300"]);
+        assert_eq!((cli.config.tolerance_ms, cli.config.min_standard_units), (300, 3));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_parse_args_hints() {
+        let args = |v: &[&str]| parse_args(v.iter().map(|s| s.to_string()));
+
+        let cli = args(&["--hints", "hints.json", "--profiles", "profiles"]).unwrap();
+        assert_eq!((cli.hints_path.as_deref(), cli.profiles_dir.as_deref()), (Some("hints.json"), Some("profiles")));
+    }
+
+    #[test]
+    fn test_read_recorded_at_file() {
+        let dir = env::temp_dir();
+        let text_path = dir.join(format!("cm-detector-recorded-at-{}.txt", process::id()));
+        let json_path = dir.join(format!("cm-detector-recorded-at-{}.json", process::id()));
+        fs::write(&text_path, "2024-04-01T21:00:00+09:00\n").unwrap();
+        fs::write(&json_path, r#"{"recorded_at": "2024-04-01T12:00:00Z", "channel": "NHK"}"#).unwrap();
+
+        let expected = Timestamp::parse_rfc3339("2024-04-01T21:00:00+09:00").unwrap();
+        let from_text = read_recorded_at_file(text_path.to_str().unwrap()).unwrap();
+        let from_json = read_recorded_at_file(json_path.to_str().unwrap()).unwrap();
+        assert_eq!(from_text, expected);
+        assert_eq!(from_json.unix_ms, expected.unix_ms);
+        assert!(read_recorded_at_file("/nonexistent/recorded_at").is_err());
+
+        fs::remove_file(text_path).unwrap();
+        fs::remove_file(json_path).unwrap();
+    }
+
+    #[test]
+    fn test_black_fusion() {
+        // 15s x 5 のCMブロック。境界のうち1つ（30000）だけ黒画面を伴わない
+        let segments: Vec<SilenceSegment> = (0..6)
+            .map(|i| SilenceSegment {
+                start_ms: i * 15000,
+                end_ms: i * 15000 + 200,
+                duration_ms: 200,
+            })
+            .collect();
+        let black_segments: Vec<BlackSegment> = [0, 15000, 45000, 60000, 75000]
+            .iter()
+            .map(|&t| BlackSegment { start_ms: t + 300, end_ms: t + 400 })
+            .collect();
+
+        let fuse = |config: &DetectorConfig| fuse_boundaries(&segments, &black_segments, &[], &[], &[], config);
+
+        let boundaries = fuse(&DetectorConfig::default());
+        assert!(boundaries[0].has(Signal::Black));
+        assert!(!boundaries[2].has(Signal::Black));
+
+        // 黒画面を伴わない無音区間は除外される
+        let config = DetectorConfig { black_mode: BlackMode::Require, ..DetectorConfig::default() };
+        let filtered = fuse(&config);
+        assert_eq!(filtered.len(), 5);
+        assert!(filtered.iter().all(|b| b.range.start != 30000));
+
+        // 一致率: 6境界中5つ
+        let blocks = detect_blocks_range_based(&segments, &DetectorConfig::default());
+        assert_eq!(blocks.len(), 1);
+        let confidence = black_confidence(&blocks[0], &boundaries);
+        assert!((confidence - 5.0 / 6.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_parse_args_black_mode() {
+        let args = |v: &[&str]| parse_args(v.iter().map(|s| s.to_string()));
+
+        assert_eq!(args(&[]).unwrap().config.black_mode, BlackMode::Boost);
+        assert_eq!(
+            args(&["--black-mode", "require"]).unwrap().config.black_mode,
+            BlackMode::Require
+        );
+        assert!(args(&["--black-mode", "always"]).is_err());
+
+        assert!(!args(&[]).unwrap().config.snap_to_scene_cuts);
+        assert!(args(&["--scene-cuts", "snap"]).unwrap().config.snap_to_scene_cuts);
+        assert!(!args(&["--scene-cuts", "off"]).unwrap().config.snap_to_scene_cuts);
+        assert!(args(&["--scene-cuts", "on"]).is_err());
+    }
+
+    #[test]
+    fn test_snap_to_scene_cuts() {
+        // 無音区間の中心はカットから最大0.4sずれているが、カットは正確に15.015s間隔
+        let segments = vec![
+            SilenceSegment { start_ms: 0, end_ms: 1000, duration_ms: 1000 },
+            SilenceSegment { start_ms: 14800, end_ms: 15600, duration_ms: 800 },
+            SilenceSegment { start_ms: 29900, end_ms: 30900, duration_ms: 1000 },
+            SilenceSegment { start_ms: 44800, end_ms: 45500, duration_ms: 700 },
+            SilenceSegment { start_ms: 59900, end_ms: 60700, duration_ms: 800 },
+            SilenceSegment { start_ms: 200000, end_ms: 201000, duration_ms: 1000 },
+        ];
+        // 最後のカットは無音区間から離れているので無視される
+        let scene_cuts = vec![700, 15715, 30730, 45360, 60460, 100000];
+
+        // デフォルトではカットは支持のみで、境界の範囲は変えない
+        let boundaries = fuse_boundaries(&segments, &[], &scene_cuts, &[], &[], &DetectorConfig::default());
+        assert!(boundaries[0].has(Signal::SceneCut));
+        assert_eq!(boundaries[0].to_silence_segment().end_ms, 1000);
+
+        let config = DetectorConfig { snap_to_scene_cuts: true, ..Default::default() };
+        let boundaries = fuse_boundaries(&segments, &[], &scene_cuts, &[], &[], &config);
+        assert!(boundaries[0].has(Signal::SceneCut));
+        assert!(!boundaries[5].has(Signal::SceneCut));
+
+        let snapped: Vec<SilenceSegment> = boundaries.iter().map(FusedBoundary::to_silence_segment).collect();
+        assert_eq!((snapped[0].start_ms, snapped[0].end_ms), (700, 700));
+        assert_eq!((snapped[1].start_ms, snapped[1].end_ms), (15715, 15715));
+        assert_eq!((snapped[5].start_ms, snapped[5].end_ms), (200000, 201000));
+
+        let blocks = detect_blocks_range_based(&snapped, &DetectorConfig::default());
+        assert_eq!(blocks.len(), 1);
+        // 境界はカットのフレーム
+        assert_eq!((blocks[0].start_ms, blocks[0].end_ms), (700, 60460));
+
+        // ブロック内の境界の根拠
+        let explained: Vec<BoundaryOutput> =
+            boundaries_in_block(&blocks[0], &boundaries).map(BoundaryOutput::from).collect();
+        assert_eq!(explained.len(), 5);
+        assert_eq!(explained[1].time_ms, 15715);
+        assert_eq!(explained[1].signals, vec![Signal::Silence, Signal::SceneCut]);
+        assert!((explained[1].score - 1.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_filter_blocks_by_loudness() {
+        // 0-60s: CM（-24 ± 1 LU）、100-160s: 本編（-45〜-20 LU）
+        let mut samples = Vec::new();
+        for i in 0..600 {
+            samples.push(LoudnessSample {
+                time_ms: i * 100,
+                momentary_lufs: -24.0 + (i % 3) as f64 - 1.0,
+                short_term_lufs: -24.0,
+            });
+        }
+        for i in 1000..1600 {
+            let lufs = if i % 2 == 0 { -45.0 } else { -20.0 };
+            samples.push(LoudnessSample { time_ms: i * 100, momentary_lufs: lufs, short_term_lufs: -30.0 });
+        }
+        let blocks = vec![block(0, 60000), block(100000, 160000), block(200000, 260000)];
+
+        // ラウドネスのない区間のブロックは判定しない
+        let filtered = filter_blocks_by_loudness(blocks.clone(), &samples, &DetectorConfig::default(), false);
+        let starts: Vec<i64> = filtered.iter().map(|b| b.start_ms).collect();
+        assert_eq!(starts, vec![0, 200000]);
+
+        let config = DetectorConfig { max_cm_loudness_range_lu: None, ..DetectorConfig::default() };
+        assert_eq!(filter_blocks_by_loudness(blocks, &samples, &config, false).len(), 3);
+    }
+
+    #[test]
+    fn test_split_blocks_at_channel_changes() {
+        // 15s x 8 のブロックのうち、60s以降は5.1chの本編
+        let segments: Vec<CmCandidate> = (0..8)
+            .map(|i| CmCandidate::new(i * 15000, (i + 1) * 15000, true, SegmentKind::Cm))
+            .collect();
+        let blocks = vec![CmBlock::new(0, 120000, segments)];
+        let changes = vec![ChannelLayoutChange {
+            time_ms: 60300,
+            from: "stereo".to_string(),
+            to: "5.1".to_string(),
+        }];
+
+        let split = split_blocks_at_channel_changes(blocks.clone(), &changes, false);
+        let bounds: Vec<(i64, i64, usize)> =
+            split.iter().map(|b| (b.start_ms, b.end_ms, b.segments.len())).collect();
+        assert_eq!(bounds, vec![(0, 60000, 4), (60000, 120000, 4)]);
+
+        // 境界付近の変化はブロックの境界と一致しているので分割しない
+        let at_edge = vec![ChannelLayoutChange { time_ms: 119500, ..changes[0].clone() }];
+        assert_eq!(split_blocks_at_channel_changes(blocks, &at_edge, false).len(), 1);
+
+        let programs = compute_program_segments(&[block(120000, 180000)], Some(0), Some(300000));
+        let programs = split_programs_at_channel_changes(programs, &changes);
+        let layouts: Vec<(i64, i64, Option<&str>)> = programs
+            .iter()
+            .map(|p| (p.start_ms, p.end_ms, p.channel_layout.as_deref()))
+            .collect();
+        assert_eq!(
+            layouts,
+            vec![
+                (0, 60300, Some("stereo")),
+                (60300, 120000, Some("5.1")),
+                (180000, 300000, Some("5.1")),
+            ]
+        );
+
+        let args = |v: &[&str]| parse_args(v.iter().map(|s| s.to_string()));
+        assert!(args(&[]).unwrap().config.split_at_channel_changes);
+        assert!(!args(&["--channel-changes", "off"]).unwrap().config.split_at_channel_changes);
+    }
+
+    #[test]
+    fn test_parse_args_input_format() {
+        let args = |v: &[&str]| parse_args(v.iter().map(|s| s.to_string()));
+
+        assert_eq!(args(&[]).unwrap().input_format, InputFormat::Ffmpeg);
+        assert_eq!(args(&["--input-format", "json"]).unwrap().input_format, InputFormat::Json);
+        assert_eq!(args(&["--input-format", "csv"]).unwrap().input_format, InputFormat::Csv);
+        assert!(args(&["--input-format", "xml"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_channels() {
+        let args = |v: &[&str]| parse_args(v.iter().map(|s| s.to_string()));
+
+        assert_eq!(args(&[]).unwrap().channel_combine, ChannelCombine::All);
+        assert_eq!(args(&["--channels", "1"]).unwrap().channel_combine, ChannelCombine::Channel(1));
+        assert!(args(&["--channels", "left"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_validation() {
+        let args = |v: &[&str]| parse_args(v.iter().map(|s| s.to_string()));
+
+        assert_eq!(args(&[]).unwrap().validation, ValidationMode::Warn);
+        assert_eq!(args(&["--validate", "repair"]).unwrap().validation, ValidationMode::Repair);
+        assert!(args(&["--validate", "fix"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_pts_epochs() {
+        let args = |v: &[&str]| parse_args(v.iter().map(|s| s.to_string()));
+
+        assert!(args(&[]).unwrap().config.split_pts_epochs);
+        assert!(!args(&["--pts-epochs", "off"]).unwrap().config.split_pts_epochs);
+    }
+
+    #[test]
+    fn test_parse_args_pcm() {
+        let args = |v: &[&str]| parse_args(v.iter().map(|s| s.to_string()));
+
+        let cli = args(&["--input-format", "pcm", "--pcm-rate", "16000", "--pcm-channels", "1", "--silence-noise", "-50dB"])
+            .unwrap();
+        assert_eq!(cli.input_format, InputFormat::Pcm);
+        assert_eq!((cli.audio.pcm.sample_rate, cli.audio.pcm.channels), (16000, 1));
+        assert_eq!(cli.audio.silence.noise_db, -50.0);
+        assert!(args(&["--pcm-format", "u8"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_silence_noise() {
+        let args = |v: &[&str]| parse_args(v.iter().map(|s| s.to_string()));
+
+        assert!(!args(&[]).unwrap().audio.silence.adaptive);
+        assert_eq!(args(&[]).unwrap().log_noise_db, None);
+        let cli = args(&["--silence-noise", "auto", "--log-noise", "-50dB"]).unwrap();
+        assert!(cli.audio.silence.adaptive);
+        assert_eq!(cli.log_noise_db, Some(-50.0));
+        assert!(args(&["--silence-noise", "loud"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_rescan() {
+        let args = |v: &[&str]| parse_args(v.iter().map(|s| s.to_string()));
+
+        assert_eq!(args(&[]).unwrap().rescan, RescanMode::Off);
+        let cli = args(&["--input-format", "wav", "--input", "a.wav", "--rescan", "native"]).unwrap();
+        assert_eq!(cli.rescan, RescanMode::Native);
+        assert!(args(&["--rescan", "native"]).is_err());
+        assert!(args(&["--rescan", "ffmpeg"]).is_err());
+        assert!(args(&["--rescan", "ffmpeg", "--rescan-source", "video.ts"]).is_ok());
+    }
 }
@@ -0,0 +1,652 @@
+// ffmpegのログ出力のパース
+
+use std::collections::BTreeMap;
+
+use crate::loudness::LoudnessSample;
+use crate::threshold::ThresholdReport;
+use crate::timestamp::Timestamp;
+use crate::validate::{Anomaly, AnomalyKind};
+use crate::{BlackSegment, ChannelLayoutChange, JST_OFFSET_SEC, SilenceSegment};
+
+// silence_duration と silence_end - silence_start の許容誤差（ffmpeg は有効数字6桁で出力するため、
+// 数時間の録画では 0.1 秒単位に丸められる）
+const DURATION_TOLERANCE_MS: f64 = 100.0;
+// showinfo のフレームの間隔の中央値がこれ以上なら select で選択されたフレーム（シーンチェンジ）とみなす
+// （select なしの showinfo は全フレームを出力するため、間隔はフレーム長になる）
+const SELECTED_FRAME_MIN_GAP_MS: i64 = 500;
+
+/// ffmpegのログから抽出した検出結果
+#[derive(Debug, Clone, Default)]
+pub struct ParsedLog {
+    /// silencedetect の無音区間
+    pub silence_segments: Vec<SilenceSegment>,
+    /// チャンネルごとの無音区間（silencedetect=mono=1）。チャンネル番号は0始まり
+    pub channel_silences: BTreeMap<u32, Vec<SilenceSegment>>,
+    /// blackdetect の黒画面区間
+    pub black_segments: Vec<BlackSegment>,
+    /// scdet / select=scene + metadata=print / showinfo のシーンチェンジ時刻（ミリ秒）
+    pub scene_cuts: Vec<i64>,
+    /// ebur128 のラウドネス（momentary / short-term）
+    pub loudness_samples: Vec<LoudnessSample>,
+    /// 音声のチャンネルレイアウトの変化（ashowinfo / ストリーム変化メッセージ）
+    pub channel_layout_changes: Vec<ChannelLayoutChange>,
+    /// 入力の長さ（ミリ秒）
+    pub duration_ms: Option<i64>,
+    /// 録画開始時刻
+    pub recorded_at: Option<Timestamp>,
+    /// 非ASCII文字や進捗表示との連結があっても無音区間のキーを取り出せた行数
+    pub recovered_lines: usize,
+    /// 無音区間のキーを含むが値を読み取れなかった行数
+    pub unparseable_lines: usize,
+    /// silence_duration の不一致
+    pub anomalies: Vec<Anomaly>,
+    /// 内蔵の無音検出で推定した閾値（WAV / PCM で --silence-noise auto の場合）
+    pub silence_threshold: Option<ThresholdReport>,
+}
+
+/// Check if a string contains only ASCII characters
+fn is_ascii_line(line: &str) -> bool {
+    line.bytes().all(|b| b.is_ascii())
+}
+
+// FFmpeg silencedetect / blackdetect / scdet / metadata / showinfo / ebur128 / ashowinfo 出力から
+// 無音区間・黒画面区間・シーンチェンジ・ラウドネス・チャンネルレイアウトの変化をパース
+pub fn parse_ffmpeg_log(output: &str) -> ParsedLog {
+    let mut parsed = ParsedLog::default();
+    let mut current_start: Option<f64> = None;
+    let mut channel_starts: BTreeMap<u32, f64> = BTreeMap::new();
+    // silence_duration と照合する直前の無音区間（チャンネルごと）
+    let mut last_silence: BTreeMap<Option<u32>, (f64, f64)> = BTreeMap::new();
+    let mut current_black_start: Option<f64> = None;
+    let mut current_layout: Option<String> = None;
+    // 時刻を含まない行（ストリーム変化メッセージ）に使う直近の時刻
+    let mut last_time: Option<f64> = None;
+    // metadata=print のフレーム行の時刻（次の行の lavfi.scene_score と対応付ける）
+    let mut metadata_frame_time: Option<f64> = None;
+    // showinfo のフレームの時刻と、select フィルタの痕跡
+    let mut showinfo_times: Vec<i64> = Vec::new();
+    let mut saw_select = false;
+
+    // 進捗表示は \r で区切られ、ログ行と同じ物理行に連結されることがあるため \r と \n の両方で分割する
+    for raw_line in output.split('\n') {
+        let pieces: Vec<&str> = raw_line.split('\r').filter(|piece| !piece.is_empty()).collect();
+        let mut silence_events = 0;
+        // 非ASCII文字を含む行、複数のキーが連結された行
+        let mut recovered = false;
+        for &line in &pieces {
+            let (events, failures) = extract_silence_events(line);
+            parsed.unparseable_lines += (failures > 0) as usize;
+            let edges = events.iter().filter(|e| e.edge != SilenceEdge::Duration).count();
+            silence_events += edges;
+            recovered |= edges > 0 && (!is_ascii_line(line) || edges > 1);
+
+            for event in events {
+                match (event.edge, event.channel) {
+                    (SilenceEdge::Start, Some(channel)) => {
+                        channel_starts.insert(channel, event.time);
+                    }
+                    (SilenceEdge::Start, None) => current_start = Some(event.time),
+                    (SilenceEdge::End, Some(channel)) => {
+                        if let Some(start) = channel_starts.remove(&channel) {
+                            parsed
+                                .channel_silences
+                                .entry(channel)
+                                .or_default()
+                                .push(make_silence(start, event.time));
+                            last_silence.insert(Some(channel), (start, event.time));
+                        }
+                    }
+                    (SilenceEdge::End, None) => {
+                        if let Some(start) = current_start.take() {
+                            parsed.silence_segments.push(make_silence(start, event.time));
+                            last_silence.insert(None, (start, event.time));
+                        }
+                    }
+                    (SilenceEdge::Duration, channel) => {
+                        if let Some((start, end)) = last_silence.remove(&channel)
+                            && ((end - start) - event.time).abs() * 1000.0 > DURATION_TOLERANCE_MS
+                        {
+                            parsed.anomalies.push(Anomaly {
+                                kind: AnomalyKind::DurationMismatch,
+                                time_ms: (start * 1000.0) as i64,
+                                detail: format!(
+                                    "silence_duration {} differs from {} - {}",
+                                    event.time, end, start
+                                ),
+                            });
+                        }
+                    }
+                }
+            }
+
+            // blackdetect は "black_start:1.2 black_end:2.3 black_duration:1.1" を1行で出力する
+            if let Some(start) = extract_timestamp(line, "black_start:") {
+                current_black_start = Some(start);
+            }
+            if let (Some(start), Some(end)) = (current_black_start, extract_timestamp(line, "black_end:")) {
+                parsed.black_segments.push(BlackSegment {
+                    start_ms: (start * 1000.0) as i64,
+                    end_ms: (end * 1000.0) as i64,
+                });
+                current_black_start = None;
+            }
+
+            // scdet は "lavfi.scd.score: 45.1, lavfi.scd.time: 12.345" を出力する
+            let is_ashowinfo = line.contains("ashowinfo");
+            if let Some(time) = extract_timestamp(line, "lavfi.scd.time:") {
+                parsed.scene_cuts.push((time * 1000.0).round() as i64);
+            }
+            // select='gt(scene,0.3)',metadata=print は "frame:42 pts:... pts_time:12.345" の次の行に
+            // "lavfi.scene_score=0.45" を出力する
+            if line.contains("lavfi.scene_score=") {
+                if let Some(time) = metadata_frame_time.take() {
+                    parsed.scene_cuts.push((time * 1000.0).round() as i64);
+                }
+            } else if line.contains("frame:") {
+                metadata_frame_time = extract_timestamp(line, "pts_time:");
+            }
+            // select='gt(scene,0.3)',showinfo は選択されたフレームの "n: 0 pts: ... pts_time:12.345" を出力する
+            // select の後かどうかは行からは分からないため、ログ全体で判定する（ループの後）
+            saw_select |= line.contains("Parsed_select_") || line.contains("gt(scene");
+            if line.contains("showinfo")
+                && !is_ashowinfo
+                && let Some(time) = extract_timestamp(line, "pts_time:")
+            {
+                showinfo_times.push((time * 1000.0).round() as i64);
+            }
+
+            // ebur128 は "t: 1.2  TARGET:-23 LUFS    M: -25.3 S: -24.7  I: -24.1 LUFS ..." を出力する
+            if line.contains("ebur128")
+                && let (Some(time), Some(momentary), Some(short_term)) = (
+                    extract_timestamp(line, " t:"),
+                    extract_timestamp(line, " M:"),
+                    extract_timestamp(line, " S:"),
+                )
+            {
+                parsed.loudness_samples.push(LoudnessSample {
+                    time_ms: (time * 1000.0).round() as i64,
+                    momentary_lufs: momentary,
+                    short_term_lufs: short_term,
+                });
+            }
+
+            // 直近の時刻を更新
+            if let Some(time) = ["silence_start:", "silence_end:", "pts_time:"]
+                .iter()
+                .find_map(|key| extract_timestamp(line, key))
+                .or_else(|| extract_progress_time(line))
+            {
+                last_time = Some(time);
+            }
+
+            // ashowinfo は "pts_time:12.345 fmt:fltp channels:2 chlayout:stereo ..." を出力する
+            // ストリーム変化は "Input stream #0:1 frame changed from rate:48000 ... chl:stereo to rate:48000 ... chl:5.1"
+            let layout = if is_ashowinfo {
+                extract_word(line, "chlayout:").or_else(|| extract_word(line, "channel_layout:"))
+            } else if line.contains("frame changed from") {
+                line.split(" to ").nth(1).and_then(|to| extract_word(to, "chl:"))
+            } else {
+                None
+            };
+            if let (Some(layout), Some(time)) = (layout, last_time) {
+                if let Some(previous) = &current_layout
+                    && *previous != layout
+                {
+                    parsed.channel_layout_changes.push(ChannelLayoutChange {
+                        time_ms: (time * 1000.0).round() as i64,
+                        from: previous.clone(),
+                        to: layout.clone(),
+                    });
+                }
+                current_layout = Some(layout);
+            }
+        }
+        if silence_events > 0 && (pieces.len() > 1 || recovered) {
+            parsed.recovered_lines += 1;
+        }
+    }
+
+    if saw_select || is_sparse(&showinfo_times) {
+        parsed.scene_cuts.extend(showinfo_times);
+    }
+    parsed.scene_cuts.sort_unstable();
+    parsed.scene_cuts.dedup();
+
+    parsed.duration_ms = parse_input_duration_ms(output);
+    parsed.recorded_at = parse_creation_time(output);
+    parsed
+}
+
+/// showinfo のフレームの間隔の中央値が SELECTED_FRAME_MIN_GAP_MS 以上か（select で間引かれたフレーム）
+fn is_sparse(times_ms: &[i64]) -> bool {
+    let mut gaps: Vec<i64> = times_ms.windows(2).map(|pair| (pair[1] - pair[0]).abs()).collect();
+    if gaps.is_empty() {
+        return false;
+    }
+    gaps.sort_unstable();
+    gaps[gaps.len() / 2] >= SELECTED_FRAME_MIN_GAP_MS
+}
+
+fn make_silence(start: f64, end: f64) -> SilenceSegment {
+    SilenceSegment {
+        start_ms: (start * 1000.0) as i64,
+        end_ms: (end * 1000.0) as i64,
+        duration_ms: ((end - start) * 1000.0) as i64,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SilenceEdge {
+    Start,
+    End,
+    /// silence_end に続く silence_duration（照合用）
+    Duration,
+}
+
+impl SilenceEdge {
+    fn key(self) -> &'static str {
+        match self {
+            SilenceEdge::Start => "silence_start",
+            SilenceEdge::End => "silence_end",
+            SilenceEdge::Duration => "silence_duration",
+        }
+    }
+}
+
+/// silencedetect の無音区間の開始・終了
+#[derive(Debug, Clone, Copy)]
+struct SilenceEvent {
+    edge: SilenceEdge,
+    /// チャンネル番号（0始まり、mono=1 の場合のみ）
+    channel: Option<u32>,
+    /// 秒
+    time: f64,
+}
+
+// 行内の silencedetect のキーを位置順に全て抽出する。戻り値は (イベント, 値を読み取れなかったキーの数)
+// ログの "silence_start: 12.3" / "channel: 0 | silence_start: 12.3"（mono=1）と、
+// ametadata=print の "lavfi.silence_start=12.3" / "lavfi.silence_start.1=12.3"（1始まり）に対応
+// 前後の非ASCII文字や進捗表示の連結は無視する
+fn extract_silence_events(line: &str) -> (Vec<SilenceEvent>, usize) {
+    let mut keys: Vec<(usize, SilenceEdge)> = [SilenceEdge::Start, SilenceEdge::End, SilenceEdge::Duration]
+        .into_iter()
+        .flat_map(|edge| line.match_indices(edge.key()).map(move |(pos, _)| (pos, edge)))
+        .collect();
+    keys.sort_by_key(|&(pos, _)| pos);
+
+    let mut events = Vec::new();
+    let mut failures = 0;
+    for (pos, edge) in keys {
+        let rest = &line[pos + edge.key().len()..];
+        let event = if line[..pos].ends_with("lavfi.") {
+            parse_metadata_value(rest).map(|(channel, time)| SilenceEvent { edge, channel, time })
+        } else {
+            // 直前の "channel: N |" がこのキーのチャンネル
+            let channel = line[..pos]
+                .rsplit_once("channel:")
+                .and_then(|(_, tag)| tag.split('|').next()?.trim().parse().ok());
+            parse_log_value(rest).map(|time| SilenceEvent { edge, channel, time })
+        };
+        match event {
+            Some(event) => events.push(event),
+            None => failures += 1,
+        }
+    }
+    (events, failures)
+}
+
+// ametadata=print の "=12.3" / ".1=12.3" を読み取る。戻り値は (チャンネル番号（0始まり）, 秒)
+fn parse_metadata_value(rest: &str) -> Option<(Option<u32>, f64)> {
+    let (suffix, value) = rest.split_once('=')?;
+    let channel = match suffix.strip_prefix('.') {
+        Some(channel) => Some(channel.parse::<u32>().ok()?.checked_sub(1)?),
+        None if suffix.is_empty() => None,
+        None => return None,
+    };
+    Some((channel, parse_number(value)?))
+}
+
+// ログの ": 12.3" を読み取る
+fn parse_log_value(rest: &str) -> Option<f64> {
+    parse_number(rest.strip_prefix(':')?)
+}
+
+// 先頭の数値を読み取る（後続の "|"、","、非ASCII文字等は無視）
+fn parse_number(value: &str) -> Option<f64> {
+    let value = value.trim_start();
+    let end = value
+        .find(|c: char| !(c.is_ascii_digit() || matches!(c, '.' | '-')))
+        .unwrap_or(value.len());
+    value[..end].parse().ok()
+}
+
+/// チャンネルごとの無音区間の統合方法
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ChannelCombine {
+    /// 全チャンネルが無音の区間（mono=0 と同じ）
+    #[default]
+    All,
+    /// いずれかのチャンネルが無音の区間
+    Any,
+    /// 指定したチャンネル（0始まり）の無音区間
+    Channel(u32),
+}
+
+impl ChannelCombine {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "all" => Some(ChannelCombine::All),
+            "any" => Some(ChannelCombine::Any),
+            _ => name.parse().ok().map(ChannelCombine::Channel),
+        }
+    }
+}
+
+/// チャンネルごとの無音区間を1つの無音区間リストに統合
+pub fn combine_channel_silences(
+    channel_silences: &BTreeMap<u32, Vec<SilenceSegment>>,
+    combine: ChannelCombine,
+) -> Vec<SilenceSegment> {
+    match combine {
+        ChannelCombine::All => intersect_channel_silences(channel_silences),
+        ChannelCombine::Any => union_channel_silences(channel_silences),
+        ChannelCombine::Channel(channel) => channel_silences.get(&channel).cloned().unwrap_or_default(),
+    }
+}
+
+// いずれかのチャンネルが無音の区間（重なる区間を結合）
+fn union_channel_silences(channel_silences: &BTreeMap<u32, Vec<SilenceSegment>>) -> Vec<SilenceSegment> {
+    let mut all: Vec<&SilenceSegment> = channel_silences.values().flatten().collect();
+    all.sort_by_key(|s| s.start_ms);
+    let mut result: Vec<SilenceSegment> = Vec::new();
+    for s in all {
+        match result.last_mut() {
+            Some(last) if s.start_ms <= last.end_ms => {
+                last.end_ms = last.end_ms.max(s.end_ms);
+                last.duration_ms = last.end_ms - last.start_ms;
+            }
+            _ => result.push(s.clone()),
+        }
+    }
+    result
+}
+
+// 全チャンネルが無音の区間（各チャンネルの無音区間の共通部分）
+fn intersect_channel_silences(channel_silences: &BTreeMap<u32, Vec<SilenceSegment>>) -> Vec<SilenceSegment> {
+    let mut channels = channel_silences.values();
+    let Some(first) = channels.next() else {
+        return Vec::new();
+    };
+    channels.fold(first.clone(), |acc, segments| {
+        let mut result = Vec::new();
+        let (mut i, mut j) = (0, 0);
+        while i < acc.len() && j < segments.len() {
+            let start_ms = acc[i].start_ms.max(segments[j].start_ms);
+            let end_ms = acc[i].end_ms.min(segments[j].end_ms);
+            if start_ms < end_ms {
+                result.push(SilenceSegment {
+                    start_ms,
+                    end_ms,
+                    duration_ms: end_ms - start_ms,
+                });
+            }
+            if acc[i].end_ms < segments[j].end_ms {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+        result
+    })
+}
+
+// タイムスタンプを抽出
+fn extract_timestamp(line: &str, key: &str) -> Option<f64> {
+    line.split(key)
+        .nth(1)?
+        .split_whitespace()
+        .next()?
+        .trim_end_matches(',')
+        .parse::<f64>()
+        .ok()
+}
+
+// キーの直後の単語を抽出
+fn extract_word(line: &str, key: &str) -> Option<String> {
+    let word = line.split(key).nth(1)?.split_whitespace().next()?;
+    Some(word.to_string())
+}
+
+// 進捗表示の "time=00:12:34.56" を秒に変換
+fn extract_progress_time(line: &str) -> Option<f64> {
+    let value = line.split("time=").nth(1)?.split_whitespace().next()?;
+    let mut parts = value.split(':');
+    let hours: f64 = parts.next()?.parse().ok()?;
+    let minutes: f64 = parts.next()?.parse().ok()?;
+    let seconds: f64 = parts.next()?.parse().ok()?;
+    Some(hours * 3600.0 + minutes * 60.0 + seconds)
+}
+
+/// ffmpegのログから入力の長さ（"Duration: 00:30:00.03, ..."）を取得
+pub fn parse_input_duration_ms(output: &str) -> Option<i64> {
+    output.lines().find_map(|line| {
+        let value = line.split("Duration:").nth(1)?.split(',').next()?.trim();
+        let mut parts = value.split(':');
+        let hours: f64 = parts.next()?.parse().ok()?;
+        let minutes: f64 = parts.next()?.parse().ok()?;
+        let seconds: f64 = parts.next()?.parse().ok()?;
+        Some(((hours * 3600.0 + minutes * 60.0 + seconds) * 1000.0).round() as i64)
+    })
+}
+
+/// ffmpegのログのメタデータ（"creation_time   : 2024-04-01T12:00:00.000000Z"）から録画開始時刻を取得
+/// creation_time はUTCで記録されるため、JSTで出力する
+pub fn parse_creation_time(output: &str) -> Option<Timestamp> {
+    output.lines().find_map(|line| {
+        let (key, value) = line.split_once(':')?;
+        if key.trim() != "creation_time" {
+            return None;
+        }
+        Timestamp::parse_rfc3339(value).map(|ts| ts.with_offset(JST_OFFSET_SEC))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_log_metadata() {
+        let log = "Input #0, mpegts, from '録画.ts':
+  Duration: 00:30:00.50, start: 1.400000, bitrate: 16000 kb/s
+  Metadata:
+    creation_time   : 2024-04-01T12:00:00.000000Z
+[silencedetect @ 0x55] silence_start: 10.0
+";
+        assert_eq!(parse_input_duration_ms(log), Some(1800500));
+        // creation_time はUTC → JSTで出力
+        let start = parse_creation_time(log).unwrap();
+        assert_eq!(start.to_rfc3339(), "2024-04-01T21:00:00.000+09:00");
+
+        assert_eq!(parse_input_duration_ms("Duration: N/A, bitrate: N/A"), None);
+        assert!(parse_creation_time("silence_start: 1.0").is_none());
+    }
+
+    #[test]
+    fn test_parse_silence_and_black() {
+        let log = "[silencedetect @ 0x5581] silence_start: 119.5
+[blackdetect @ 0x5582] black_start:119.8 black_end:120.3 black_duration:0.5
+[silencedetect @ 0x5581] silence_end: 120.5 | silence_duration: 1
+[silencedetect @ 0x5581] silence_start: 300
+[silencedetect @ 0x5581] silence_end: 300.4 | silence_duration: 0.4
+";
+        let parsed = parse_ffmpeg_log(log);
+        assert_eq!(parsed.silence_segments.len(), 2);
+        assert_eq!(parsed.silence_segments[0].start_ms, 119500);
+        assert_eq!(parsed.silence_segments[0].end_ms, 120500);
+        assert_eq!(parsed.black_segments.len(), 1);
+        assert_eq!(parsed.black_segments[0].start_ms, 119800);
+        assert_eq!(parsed.black_segments[0].end_ms, 120300);
+    }
+
+    #[test]
+    fn test_parse_scene_cuts() {
+        // ffmpeg -vf "scdet=threshold=10"
+        let log = "[scdet @ 0x55a0c3e0] lavfi.scd.score: 45.120, lavfi.scd.time: 120.12
+";
+        assert_eq!(parse_ffmpeg_log(log).scene_cuts, vec![120120]);
+
+        // ffmpeg -vf "select='gt(scene,0.3)',metadata=print"
+        let log = "[Parsed_metadata_1 @ 0x5581a8d4b9c0] frame:0    pts:10810800 pts_time:120.12
+[Parsed_metadata_1 @ 0x5581a8d4b9c0] lavfi.scene_score=0.451240
+[Parsed_metadata_1 @ 0x5581a8d4b9c0] frame:1    pts:12162150 pts_time:135.135
+[Parsed_metadata_1 @ 0x5581a8d4b9c0] lavfi.scene_score=0.381002
+";
+        assert_eq!(parse_ffmpeg_log(log).scene_cuts, vec![120120, 135135]);
+
+        // ffmpeg -vf "select='gt(scene,0.3)',showinfo"（選択されたフレームのみ）
+        let log = "[Parsed_showinfo_1 @ 0x5581a8d4b9c0] n:   0 pts:10810800 pts_time:120.12  duration:   3003 time_base:1/90000
+[Parsed_showinfo_1 @ 0x5581a8d4b9c0] n:   0 pts:10810800 pts_time:120.12  pos: 56402012 fmt:yuv420p sar:1/1 s:1920x1080 i:P iskey:0 type:P checksum:E3E1B0F2 plane_checksum:[8C2F8A1A 2A7B4E6E 2A0F6A8A] mean:[98 127 129] stdev:[52.1 6.2 7.9]
+[Parsed_showinfo_1 @ 0x5581a8d4b9c0] n:   1 pts:12162150 pts_time:135.135 pos: 63205120 fmt:yuv420p sar:1/1 s:1920x1080 i:P iskey:1 type:I checksum:1A2B3C4D plane_checksum:[5E6F7A8B 9C0D1E2F 3A4B5C6D] mean:[64 128 128] stdev:[40.3 4.1 5.0]
+[mpegts @ 0x55a2] pts_time:1.0 is not a scene cut
+";
+        assert_eq!(parse_ffmpeg_log(log).scene_cuts, vec![120120, 135135]);
+
+        // select なしの showinfo は全フレームを出力するのでカットではない
+        let log = "[Parsed_showinfo_0 @ 0x5581a8d4b9c0] n:   0 pts:      0 pts_time:0       pos:      564 fmt:yuv420p sar:1/1 s:1920x1080 i:P iskey:1 type:I checksum:00000000 plane_checksum:[00000000 00000000 00000000] mean:[16 128 128] stdev:[0.0 0.0 0.0]
+[Parsed_showinfo_0 @ 0x5581a8d4b9c0] n:   1 pts:   3003 pts_time:0.0333667 pos:    39480 fmt:yuv420p sar:1/1 s:1920x1080 i:P iskey:0 type:P checksum:00000000 plane_checksum:[00000000 00000000 00000000] mean:[16 128 128] stdev:[0.0 0.0 0.0]
+[Parsed_showinfo_0 @ 0x5581a8d4b9c0] n:   2 pts:   6006 pts_time:0.0667333 pos:    41212 fmt:yuv420p sar:1/1 s:1920x1080 i:P iskey:0 type:P checksum:00000000 plane_checksum:[00000000 00000000 00000000] mean:[16 128 128] stdev:[0.0 0.0 0.0]
+";
+        assert!(parse_ffmpeg_log(log).scene_cuts.is_empty());
+    }
+
+    #[test]
+    fn test_parse_ebur128() {
+        let log = "[Parsed_ebur128_0 @ 0x55d1] t: 0.499977   TARGET:-23 LUFS    M:-120.7 S:-120.7     I: -70.0 LUFS       LRA:   0.0 LU
+[Parsed_ebur128_0 @ 0x55d1] t: 0.599977   TARGET:-23 LUFS    M: -25.3 S: -24.8     I: -24.1 LUFS       LRA:   1.2 LU
+[Parsed_ebur128_0 @ 0x55d1] Summary:
+  Integrated loudness:
+    I:         -24.1 LUFS
+";
+        let parsed = parse_ffmpeg_log(log);
+        assert_eq!(parsed.loudness_samples.len(), 2);
+        assert_eq!(parsed.loudness_samples[1].time_ms, 600);
+        assert_eq!(parsed.loudness_samples[1].momentary_lufs, -25.3);
+        assert_eq!(parsed.loudness_samples[1].short_term_lufs, -24.8);
+    }
+
+    #[test]
+    fn test_parse_channel_layout_changes() {
+        let log = "[Parsed_ashowinfo_1 @ 0x55e0] n:0 pts:0 pts_time:0 fmt:fltp channels:6 chlayout:5.1 rate:48000 nb_samples:1024
+[Parsed_ashowinfo_1 @ 0x55e0] n:1 pts:1024 pts_time:0.021333 fmt:fltp channels:6 chlayout:5.1 rate:48000 nb_samples:1024
+[Parsed_ashowinfo_1 @ 0x55e0] n:2 pts:5760000 pts_time:120.0 fmt:fltp channels:2 chlayout:stereo rate:48000 nb_samples:1024
+frame=  9000 fps=300 q=-0.0 size=N/A time=00:03:00.00 bitrate=N/A speed=  10x
+Input stream #0:1 frame changed from rate:48000 fmt:fltp ch:2 chl:stereo to rate:48000 fmt:fltp ch:6 chl:5.1
+";
+        let parsed = parse_ffmpeg_log(log);
+        assert!(parsed.scene_cuts.is_empty(), "ashowinfo is not a scene cut");
+        let changes: Vec<(i64, &str, &str)> = parsed
+            .channel_layout_changes
+            .iter()
+            .map(|c| (c.time_ms, c.from.as_str(), c.to.as_str()))
+            .collect();
+        assert_eq!(changes, vec![(120000, "5.1", "stereo"), (180000, "stereo", "5.1")]);
+    }
+
+    #[test]
+    fn test_parse_ametadata_print() {
+        // ametadata=print:file=... の出力
+        let log = "frame:5000 pts:4800000 pts_time:100
+lavfi.silence_start=100.0
+frame:5025 pts:4824000 pts_time:100.5
+lavfi.silence_end=100.5
+lavfi.silence_duration=0.5
+[Parsed_ametadata_1 @ 0x55e0] lavfi.silence_start=115.0
+[Parsed_ametadata_1 @ 0x55e0] lavfi.silence_end=115.4
+";
+        let parsed = parse_ffmpeg_log(log);
+        let bounds: Vec<(i64, i64)> = parsed.silence_segments.iter().map(|s| (s.start_ms, s.end_ms)).collect();
+        assert_eq!(bounds, vec![(100000, 100500), (115000, 115400)]);
+        assert!(parsed.channel_silences.is_empty());
+
+        // チャンネルごとのキー（1始まり）
+        let log = "lavfi.silence_start.1=10.0
+lavfi.silence_start.2=10.2
+lavfi.silence_end.1=11.0
+lavfi.silence_end.2=10.8
+";
+        let parsed = parse_ffmpeg_log(log);
+        assert!(parsed.silence_segments.is_empty());
+        assert_eq!(parsed.channel_silences[&0][0].start_ms, 10000);
+        assert_eq!(parsed.channel_silences[&1][0].start_ms, 10200);
+    }
+
+    #[test]
+    fn test_per_channel_silences() {
+        // silencedetect=mono=1 の出力は、チャンネルごとの開始・終了が交互に現れる
+        let log = "[silencedetect @ 0x55] channel: 0 | silence_start: 10
+[silencedetect @ 0x55] channel: 1 | silence_start: 10.2
+[silencedetect @ 0x55] channel: 0 | silence_end: 11 | silence_duration: 1
+[silencedetect @ 0x55] channel: 1 | silence_end: 10.8 | silence_duration: 0.6
+[silencedetect @ 0x55] channel: 1 | silence_start: 20
+[silencedetect @ 0x55] channel: 1 | silence_end: 21 | silence_duration: 1
+";
+        let parsed = parse_ffmpeg_log(log);
+        assert!(parsed.silence_segments.is_empty());
+        assert_eq!(parsed.channel_silences.len(), 2);
+
+        let bounds = |combine| {
+            combine_channel_silences(&parsed.channel_silences, combine)
+                .iter()
+                .map(|s| (s.start_ms, s.end_ms))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(bounds(ChannelCombine::All), vec![(10200, 10800)]);
+        assert_eq!(bounds(ChannelCombine::Any), vec![(10000, 11000), (20000, 21000)]);
+        assert_eq!(bounds(ChannelCombine::Channel(1)), vec![(10200, 10800), (20000, 21000)]);
+        assert!(bounds(ChannelCombine::Channel(5)).is_empty());
+
+        assert_eq!(ChannelCombine::parse("any"), Some(ChannelCombine::Any));
+        assert_eq!(ChannelCombine::parse("2"), Some(ChannelCombine::Channel(2)));
+        assert_eq!(ChannelCombine::parse("left"), None);
+    }
+
+    #[test]
+    fn test_parse_mixed_and_non_ascii_lines() {
+        // 非ASCII文字を含む行、\r 区切りの進捗表示に連結された行、値のないキー
+        let log = "[silencedetect @ 0x55] silence_start: 10 録画.ts\n\
+size=N/A time=00:00:10.50 bitrate=N/A speed=50x\r[silencedetect @ 0x55] silence_end: 10.5 | silence_duration: 0.5\n\
+[silencedetect @ 0x55] silence_start: 25\r\n\
+size=N/A time=00:00:25.40 bitrate=N/A\r[silencedetect @ 0x55] silence_end: 25.4 | silence_duration: 0.4\r\n\
+[silencedetect @ 0x55] silence_start: N/A\n";
+        let parsed = parse_ffmpeg_log(log);
+        let bounds: Vec<(i64, i64)> = parsed.silence_segments.iter().map(|s| (s.start_ms, s.end_ms)).collect();
+        assert_eq!(bounds, vec![(10000, 10500), (25000, 25400)]);
+        assert_eq!(parsed.recovered_lines, 3);
+        assert_eq!(parsed.unparseable_lines, 1);
+
+        // 1行に開始と終了が連結されている場合は位置順に処理する
+        let (events, failures) = extract_silence_events("silence_end: 5 ... silence_start: 7 チャンネル");
+        assert_eq!(failures, 0);
+        let edges: Vec<(SilenceEdge, f64)> = events.iter().map(|e| (e.edge, e.time)).collect();
+        assert_eq!(edges, vec![(SilenceEdge::End, 5.0), (SilenceEdge::Start, 7.0)]);
+    }
+
+    #[test]
+    fn test_parse_duration_mismatch() {
+        let log = "[silencedetect @ 0x55] silence_start: 10
+[silencedetect @ 0x55] silence_end: 10.5 | silence_duration: 0.5
+[silencedetect @ 0x55] silence_start: 20
+[silencedetect @ 0x55] silence_end: 21 | silence_duration: 3
+lavfi.silence_start=30
+lavfi.silence_end=30.4
+lavfi.silence_duration=0.4
+";
+        let parsed = parse_ffmpeg_log(log);
+        assert_eq!(parsed.silence_segments.len(), 3);
+        assert_eq!(parsed.recovered_lines, 0);
+        assert_eq!(parsed.anomalies.len(), 1);
+        assert_eq!(parsed.anomalies[0].kind, AnomalyKind::DurationMismatch);
+        assert_eq!(parsed.anomalies[0].time_ms, 20000);
+    }
+}
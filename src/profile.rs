@@ -0,0 +1,161 @@
+// 放送局ごとの検出パラメータ（プロファイル）
+//
+// 局によってブリッジ区間・提供クレジットの扱い・黒画面の有無・録画の余白が異なるため、
+// 局ごとのパラメータをJSONファイルにまとめて読み込む。コマンドラインの指定はプロファイルより優先される。
+
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{BlackMode, BridgeType, DetectorConfig, GapMetrics, SponsorPolicy, parse_seconds_range};
+
+/// プロファイルファイルの内容（省略した項目はデフォルトのまま）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Profile {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub channel_id: Option<String>,
+    /// --gap-metric
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gap_metric: Option<String>,
+    /// --sponsor-policy
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sponsor_policy: Option<String>,
+    /// --black-mode
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub black_mode: Option<String>,
+    /// --bridge（"station_id=20" 形式）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub bridges: Vec<String>,
+    /// --start-window（"2-30" 形式）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_window: Option<String>,
+    /// --end-window（秒）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end_window_sec: Option<f64>,
+    /// --break-positions（番組の長さに対するパーセント）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub break_positions: Vec<f64>,
+    /// --tolerance（ミリ秒）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tolerance_ms: Option<i64>,
+    /// --short-units（秒、空の配列で短時間単位を使わない）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub short_units: Option<Vec<f64>>,
+    /// --min-standard-units
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_standard_units: Option<usize>,
+    /// --min-block-duration（秒）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_block_duration_sec: Option<f64>,
+}
+
+impl Profile {
+    /// 検出パラメータに反映
+    pub fn apply(&self, config: &mut DetectorConfig) -> Result<(), String> {
+        if let Some(value) = &self.gap_metric {
+            config.gap_metrics =
+                GapMetrics::parse(value).ok_or_else(|| format!("Unknown gap metric in profile: {}", value))?;
+        }
+        if let Some(value) = &self.sponsor_policy {
+            config.sponsor_policy = SponsorPolicy::parse(value)
+                .ok_or_else(|| format!("Unknown sponsor policy in profile: {}", value))?;
+        }
+        if let Some(value) = &self.black_mode {
+            config.black_mode =
+                BlackMode::parse(value).ok_or_else(|| format!("Unknown black mode in profile: {}", value))?;
+        }
+        for value in &self.bridges {
+            let bridge =
+                BridgeType::parse(value).ok_or_else(|| format!("Invalid bridge in profile: {}", value))?;
+            config.bridges.push(bridge);
+        }
+        if let Some(value) = &self.start_window {
+            config.start_offset_window = parse_seconds_range(value)
+                .ok_or_else(|| format!("Invalid start window in profile: {}", value))?;
+        }
+        if let Some(seconds) = self.end_window_sec {
+            if !seconds.is_finite() || seconds < 0.0 {
+                return Err(format!("Invalid end window in profile: {}", seconds));
+            }
+            config.end_offset_window_ms = (seconds * 1000.0).round() as i64;
+        }
+        if !self.break_positions.is_empty() {
+            if self.break_positions.iter().any(|p| !(0.0..=100.0).contains(p)) {
+                return Err("Invalid break positions in profile (expected percentages)".to_string());
+            }
+            config.break_positions = self.break_positions.iter().map(|p| p / 100.0).collect();
+        }
+        if let Some(tolerance_ms) = self.tolerance_ms {
+            if tolerance_ms < 0 {
+                return Err(format!("Invalid tolerance in profile: {}", tolerance_ms));
+            }
+            config.tolerance_ms = tolerance_ms;
+        }
+        if let Some(units) = &self.short_units {
+            if units.iter().any(|u| !u.is_finite() || *u <= 0.0) {
+                return Err("Invalid short units in profile (expected positive seconds)".to_string());
+            }
+            config.short_units = units.clone();
+        }
+        if let Some(units) = self.min_standard_units {
+            config.min_standard_units = units;
+        }
+        if let Some(seconds) = self.min_block_duration_sec {
+            if !seconds.is_finite() || seconds < 0.0 {
+                return Err(format!("Invalid block duration in profile: {}", seconds));
+            }
+            config.min_block_duration_sec = seconds;
+        }
+        Ok(())
+    }
+}
+
+/// プロファイルファイルを読み込む
+pub fn read_profile(path: &Path) -> Result<Profile, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read profile {}: {}", path.display(), e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Invalid profile {}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_profile() {
+        let profile: Profile = serde_json::from_str(
+            r#"{"channel_id": "BS11", "sponsor_policy": "separate", "bridges": ["station_id=20"], "start_window": "2-30"}"#,
+        )
+        .unwrap();
+        let mut config = DetectorConfig::default();
+        profile.apply(&mut config).unwrap();
+        assert_eq!(config.sponsor_policy, SponsorPolicy::Separate);
+        assert_eq!(config.bridges.len(), 1);
+        assert_eq!(config.start_offset_window.end, 30000);
+        assert_eq!(config.black_mode, BlackMode::Boost);
+
+        // train で学習したパラメータ
+        let trained: Profile =
+            serde_json::from_str(r#"{"tolerance_ms": 700, "short_units": [], "min_standard_units": 1}"#).unwrap();
+        trained.apply(&mut config).unwrap();
+        assert_eq!(config.tolerance_ms, 700);
+        assert!(config.short_units.is_empty());
+        assert_eq!(config.min_standard_units, 1);
+        assert_eq!(config.min_block_duration_sec, 60.0);
+
+        let invalid: Profile = serde_json::from_str(r#"{"black_mode": "maybe"}"#).unwrap();
+        assert!(invalid.apply(&mut DetectorConfig::default()).is_err());
+        let invalid: Profile = serde_json::from_str(r#"{"end_window_sec": -30}"#).unwrap();
+        assert!(invalid.apply(&mut DetectorConfig::default()).is_err());
+        let invalid: Profile = serde_json::from_str(r#"{"min_block_duration_sec": -60}"#).unwrap();
+        assert!(invalid.apply(&mut DetectorConfig::default()).is_err());
+        // JSON では表せないが、train が書き出すプロファイルは構造体から作られる
+        for invalid in [
+            Profile { short_units: Some(vec![5.0, f64::NAN]), ..Profile::default() },
+            Profile { min_block_duration_sec: Some(f64::NAN), ..Profile::default() },
+        ] {
+            assert!(invalid.apply(&mut DetectorConfig::default()).is_err());
+        }
+    }
+}
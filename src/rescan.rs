@@ -0,0 +1,225 @@
+// 境界の取りこぼしが疑われる範囲の再解析（マルチパス検出）
+//
+// silencedetect の d=0.3 より短い無音で境界を取りこぼすと、15+15秒が30秒のセグメントになって
+// 標準単位が減ったり、15+10秒が25秒の間隔になってチェーンが切れたりする。
+// ブロックの内側・端にある単位の和に近い間隔を見つけ、その範囲だけを感度を上げて再解析する。
+
+use std::fs::File;
+use std::io::Seek;
+use std::process::Command;
+
+use serde::Serialize;
+
+use crate::audio::{self, PcmFormat, SilenceParams};
+use crate::parser::parse_ffmpeg_log;
+use crate::{CmBlock, MAX_STANDARD_UNITS, STANDARD_UNIT_SEC, SilenceSegment, TOLERANCE_MS};
+
+const RESCAN_MIN_DURATION_SEC: f64 = 0.1; // 再解析での無音区間の最小長（silencedetect の d）
+const RESCAN_NOISE_STEP_DB: f64 = 5.0; // 再解析で閾値を上げる幅
+const UNIT_SUM_STEP_MS: i64 = 5000; // 単位の和とみなす間隔の刻み（5/10/15秒の和）
+const MIN_SUSPECT_GAP_MS: i64 = 20000; // これ以上の間隔を2つ以上の単位の和とみなす
+
+/// 再解析の方法
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RescanMode {
+    #[default]
+    Off,
+    /// --input の WAV / PCM ファイルの該当範囲を内蔵の無音検出で再解析する
+    Native,
+    /// --rescan-source の動画を ffmpeg -ss/-t で再解析する
+    Ffmpeg,
+}
+
+impl RescanMode {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "off" => Some(RescanMode::Off),
+            "native" => Some(RescanMode::Native),
+            "ffmpeg" => Some(RescanMode::Ffmpeg),
+            _ => None,
+        }
+    }
+}
+
+/// 再解析した範囲のJSON出力
+#[derive(Debug, Clone, Serialize)]
+pub struct RescanWindow {
+    pub start_ms: i64,
+    pub end_ms: i64,
+    /// 新たに見つかった無音区間の数
+    pub found: usize,
+}
+
+/// 間隔が2つ以上の単位の和（20秒〜75秒の5秒刻み ± TOLERANCE_MS）に近いか
+fn is_unit_sum(gap_ms: i64) -> bool {
+    let max_ms = MAX_STANDARD_UNITS * (STANDARD_UNIT_SEC * 1000.0) as i64;
+    if !(MIN_SUSPECT_GAP_MS - TOLERANCE_MS..=max_ms + TOLERANCE_MS).contains(&gap_ms) {
+        return false;
+    }
+    let remainder = gap_ms.rem_euclid(UNIT_SUM_STEP_MS);
+    remainder.min(UNIT_SUM_STEP_MS - remainder) <= TOLERANCE_MS
+}
+
+/// 再解析する範囲: ブロックの内側または端に接する、単位の和に近い間隔の非無音部分
+/// segments はチェーン検出の入力（時刻順）、blocks は最終フィルタ前のブロック
+pub fn suspect_windows(segments: &[SilenceSegment], blocks: &[CmBlock]) -> Vec<RescanWindow> {
+    let touches_block = |time_ms: i64| {
+        blocks
+            .iter()
+            .any(|b| (b.start_ms - TOLERANCE_MS..=b.end_ms + TOLERANCE_MS).contains(&time_ms))
+    };
+    segments
+        .windows(2)
+        .filter(|pair| is_unit_sum(pair[1].center_ms() - pair[0].center_ms()))
+        .filter(|pair| touches_block(pair[0].center_ms()) || touches_block(pair[1].center_ms()))
+        .map(|pair| RescanWindow {
+            start_ms: pair[0].end_ms,
+            end_ms: pair[1].start_ms,
+            found: 0,
+        })
+        .filter(|w| w.end_ms > w.start_ms)
+        .collect()
+}
+
+/// 感度を上げた無音判定のパラメータ
+pub fn relaxed_params(noise_db: f64) -> SilenceParams {
+    SilenceParams {
+        noise_db: noise_db + RESCAN_NOISE_STEP_DB,
+        min_duration_sec: RESCAN_MIN_DURATION_SEC,
+        adaptive: false,
+    }
+}
+
+/// WAV / PCM ファイルの各範囲を再解析
+pub fn rescan_pcm_file(
+    path: &str,
+    wav: bool,
+    pcm: PcmFormat,
+    windows: &mut [RescanWindow],
+    params: SilenceParams,
+) -> Result<Vec<SilenceSegment>, String> {
+    let mut file = File::open(path).map_err(|e| format!("Failed to open {}: {}", path, e))?;
+    let (format, data_start) = if wav {
+        let (format, _) = audio::read_wav_header(&mut file)?;
+        let position = file
+            .stream_position()
+            .map_err(|e| format!("Failed to read {}: {}", path, e))?;
+        (format, position)
+    } else {
+        (pcm, 0)
+    };
+
+    let mut found = Vec::new();
+    for window in windows.iter_mut() {
+        let silences =
+            audio::detect_silence_range(&mut file, data_start, format, window.start_ms, window.end_ms, params)?;
+        found.extend(inside_window(window, silences));
+    }
+    Ok(found)
+}
+
+/// 動画の各範囲を ffmpeg の silencedetect で再解析
+pub fn rescan_with_ffmpeg(
+    path: &str,
+    windows: &mut [RescanWindow],
+    params: SilenceParams,
+) -> Result<Vec<SilenceSegment>, String> {
+    let filter = format!("silencedetect=n={}dB:d={}", params.noise_db, params.min_duration_sec);
+    let mut found = Vec::new();
+    for window in windows.iter_mut() {
+        let output = Command::new("ffmpeg")
+            .args(["-hide_banner", "-nostats"])
+            .args(["-ss", &seconds(window.start_ms), "-t", &seconds(window.end_ms - window.start_ms)])
+            .args(["-i", path, "-vn", "-af", &filter, "-f", "null", "-"])
+            .output()
+            .map_err(|e| format!("Failed to run ffmpeg: {}", e))?;
+        if !output.status.success() {
+            return Err(format!("ffmpeg failed for {} ({})", path, output.status));
+        }
+        // 入力側の -ss では出力の時刻が0から始まる
+        let silences = parse_ffmpeg_log(&String::from_utf8_lossy(&output.stderr))
+            .silence_segments
+            .into_iter()
+            .map(|s| SilenceSegment {
+                start_ms: s.start_ms + window.start_ms,
+                end_ms: s.end_ms + window.start_ms,
+                duration_ms: s.duration_ms,
+            })
+            .collect();
+        found.extend(inside_window(window, silences));
+    }
+    Ok(found)
+}
+
+fn seconds(ms: i64) -> String {
+    format!("{}.{:03}", ms / 1000, ms % 1000)
+}
+
+/// 範囲の端に接する無音区間は既存の無音区間の続きとして除く
+fn inside_window(window: &mut RescanWindow, silences: Vec<SilenceSegment>) -> Vec<SilenceSegment> {
+    let inside: Vec<SilenceSegment> = silences
+        .into_iter()
+        .filter(|s| s.start_ms > window.start_ms && s.end_ms < window.end_ms)
+        .collect();
+    window.found = inside.len();
+    inside
+}
+
+/// 再解析で見つかった無音区間を既存の無音区間に加える（重なるものは除く）。戻り値は追加した数
+pub fn merge_silences(segments: &mut Vec<SilenceSegment>, found: Vec<SilenceSegment>) -> usize {
+    let mut added = 0;
+    for s in found {
+        if segments.iter().any(|e| s.range().intersect(&e.range()).is_some()) {
+            continue;
+        }
+        segments.push(s);
+        added += 1;
+    }
+    segments.sort_by_key(|s| s.start_ms);
+    added
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn silence(start_ms: i64, end_ms: i64) -> SilenceSegment {
+        SilenceSegment {
+            start_ms,
+            end_ms,
+            duration_ms: end_ms - start_ms,
+        }
+    }
+
+    #[test]
+    fn test_suspect_windows() {
+        assert!(is_unit_sum(30000) && is_unit_sum(25300) && is_unit_sum(19600));
+        assert!(!is_unit_sum(15000) && !is_unit_sum(27500) && !is_unit_sum(90000));
+
+        // 15s x 4 のブロックの後に 25s（15+10 の境界を取りこぼし）、離れた場所に 30s の間隔
+        let segments: Vec<SilenceSegment> = [0, 15000, 30000, 45000, 60000, 85000, 500000, 530000]
+            .iter()
+            .map(|&t| silence(t - 250, t + 250))
+            .collect();
+        let blocks = vec![CmBlock::new(0, 60000, Vec::new())];
+        let windows = suspect_windows(&segments, &blocks);
+        let bounds: Vec<(i64, i64)> = windows.iter().map(|w| (w.start_ms, w.end_ms)).collect();
+        assert_eq!(bounds, vec![(60250, 84750)]);
+    }
+
+    #[test]
+    fn test_merge_silences() {
+        let mut segments = vec![silence(0, 500), silence(30000, 30500)];
+        let mut window = RescanWindow { start_ms: 500, end_ms: 30000, found: 0 };
+        let found = inside_window(
+            &mut window,
+            vec![silence(500, 700), silence(15000, 15150), silence(29900, 30000)],
+        );
+        assert_eq!(window.found, 1);
+        assert_eq!(merge_silences(&mut segments, found), 1);
+        let starts: Vec<i64> = segments.iter().map(|s| s.start_ms).collect();
+        assert_eq!(starts, vec![0, 15000, 30000]);
+        // 既存の無音区間と重なるものは追加しない
+        assert_eq!(merge_silences(&mut segments, vec![silence(15100, 15200)]), 0);
+    }
+}
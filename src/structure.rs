@@ -0,0 +1,263 @@
+// 番組構成（CM枠の数・位置）のモデル
+//
+// 30分のアニメならOP後・中盤・ED後のように、CM枠の数と位置はおおよそ決まっている。
+// 想定される位置の近くで最終フィルタに落ちた弱いブロックを救済し、想定外のブロックに印を付け、
+// 検出結果がモデルにどれだけ合うかを fit_score として出力する。
+
+use serde::Serialize;
+
+use crate::{CmBlock, Range, count_standard_units};
+
+const POSITION_WINDOW_RATIO: f64 = 0.05; // 想定位置とブロックの中心のずれの許容範囲（番組の長さに対する割合）
+const RESCUE_MIN_STANDARD_UNITS: usize = 1; // 救済するブロックに必要な標準単位の数
+const RESCUE_MIN_DURATION_SEC: f64 = 30.0; // 救済するブロックの最小長
+
+/// 想定されるCM枠
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StructureModel {
+    /// CM枠の数（位置を指定しない場合）
+    pub break_count: Option<usize>,
+    /// CM枠の位置（番組の長さに対する割合 0.0〜1.0）
+    pub break_positions: Vec<f64>,
+}
+
+impl StructureModel {
+    pub fn is_empty(&self) -> bool {
+        self.break_count.is_none() && self.break_positions.is_empty()
+    }
+
+    fn expected_breaks(&self) -> usize {
+        if self.break_positions.is_empty() {
+            self.break_count.unwrap_or(0)
+        } else {
+            self.break_positions.len()
+        }
+    }
+}
+
+/// ブロックとモデルの対応
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BreakFit {
+    /// 想定されるCM枠に対応する
+    Expected,
+    /// 最終フィルタに落ちたが、想定されるCM枠の位置にあるため採用した
+    Rescued,
+    /// 想定されるCM枠に対応しない
+    Unexpected,
+}
+
+/// モデルとの適合度のJSON出力
+#[derive(Debug, Clone, Serialize)]
+pub struct StructureReport {
+    pub expected_breaks: usize,
+    pub found_breaks: usize,
+    pub rescued_breaks: usize,
+    pub unexpected_breaks: usize,
+    /// 0.0〜1.0（1.0 で想定どおり）
+    pub fit_score: f64,
+}
+
+fn can_rescue(block: &CmBlock, accepted: &[CmBlock]) -> bool {
+    count_standard_units(block) >= RESCUE_MIN_STANDARD_UNITS
+        && block.duration_sec >= RESCUE_MIN_DURATION_SEC
+        && !accepted
+            .iter()
+            .any(|b| block.start_ms < b.end_ms && b.start_ms < block.end_ms)
+}
+
+fn center_ms(block: &CmBlock) -> i64 {
+    (block.start_ms + block.end_ms) / 2
+}
+
+/// モデルを適用する
+/// blocks は最終フィルタ後のブロック、weak は最終フィルタに落ちたブロック、runtime は番組の範囲。
+/// runtime の外にあるブロック（録画の余白）は対象外
+pub fn apply_structure(
+    blocks: &mut Vec<CmBlock>,
+    weak: Vec<CmBlock>,
+    model: &StructureModel,
+    runtime: Range,
+) -> StructureReport {
+    let in_runtime = |block: &CmBlock| (runtime.start..=runtime.end).contains(&center_ms(block));
+    let mut weak: Vec<CmBlock> = weak.into_iter().filter(|b| in_runtime(b)).collect();
+    let targets: Vec<usize> = (0..blocks.len()).filter(|&i| in_runtime(&blocks[i])).collect();
+    let expected = model.expected_breaks();
+
+    let matched_score = if model.break_positions.is_empty() {
+        fit_count(blocks, &targets, &mut weak, expected)
+    } else {
+        fit_positions(blocks, &targets, &mut weak, &model.break_positions, runtime)
+    };
+
+    blocks.sort_by_key(|b| b.start_ms);
+    let count = |fit: BreakFit| blocks.iter().filter(|b| b.structure == Some(fit)).count();
+    let unexpected = count(BreakFit::Unexpected);
+    let denominator = (expected + unexpected).max(1) as f64;
+    StructureReport {
+        expected_breaks: expected,
+        found_breaks: count(BreakFit::Expected) + count(BreakFit::Rescued) + unexpected,
+        rescued_breaks: count(BreakFit::Rescued),
+        unexpected_breaks: unexpected,
+        fit_score: ((matched_score / denominator) * 1000.0).round() / 1000.0,
+    }
+}
+
+/// 数のみのモデル: 不足分は強い（標準単位が多く長い）弱いブロックから救済し、超過分は弱いブロックから想定外とする
+/// 戻り値は対応したCM枠の数
+fn fit_count(blocks: &mut Vec<CmBlock>, targets: &[usize], weak: &mut Vec<CmBlock>, expected: usize) -> f64 {
+    let mut order = targets.to_vec();
+    order.sort_by(|&a, &b| strength(&blocks[b]).total_cmp(&strength(&blocks[a])));
+    for (rank, &i) in order.iter().enumerate() {
+        blocks[i].structure = Some(if rank < expected { BreakFit::Expected } else { BreakFit::Unexpected });
+    }
+
+    let mut found = targets.len().min(expected);
+    weak.sort_by(|a, b| strength(b).total_cmp(&strength(a)));
+    for mut block in weak.drain(..) {
+        if found >= expected {
+            break;
+        }
+        if can_rescue(&block, blocks) {
+            block.structure = Some(BreakFit::Rescued);
+            blocks.push(block);
+            found += 1;
+        }
+    }
+    found as f64
+}
+
+fn strength(block: &CmBlock) -> f64 {
+    count_standard_units(block) as f64 * 1000.0 + block.duration_sec
+}
+
+/// 位置のモデル: 想定位置ごとに最も近いブロックを対応させ、なければ近くの弱いブロックを救済する
+/// 戻り値は想定位置とのずれに応じた適合度の合計
+fn fit_positions(
+    blocks: &mut Vec<CmBlock>,
+    targets: &[usize],
+    weak: &mut Vec<CmBlock>,
+    positions: &[f64],
+    runtime: Range,
+) -> f64 {
+    let length = (runtime.end - runtime.start) as f64;
+    let window_ms = (length * POSITION_WINDOW_RATIO) as i64;
+    let mut score = 0.0;
+    let mut assigned = vec![false; blocks.len()];
+    for &position in positions {
+        let expected_ms = runtime.start + (length * position) as i64;
+        let distance = |block: &CmBlock| (center_ms(block) - expected_ms).abs();
+
+        let nearest = targets
+            .iter()
+            .copied()
+            .filter(|&i| !assigned[i] && distance(&blocks[i]) <= window_ms)
+            .min_by_key(|&i| distance(&blocks[i]));
+        if let Some(i) = nearest {
+            assigned[i] = true;
+            blocks[i].structure = Some(BreakFit::Expected);
+            score += 1.0 - distance(&blocks[i]) as f64 / (window_ms.max(1) as f64 * 2.0);
+            continue;
+        }
+
+        let rescue = weak
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| distance(b) <= window_ms && can_rescue(b, blocks))
+            .min_by_key(|(_, b)| distance(b))
+            .map(|(j, _)| j);
+        if let Some(j) = rescue {
+            let mut block = weak.remove(j);
+            score += 1.0 - distance(&block) as f64 / (window_ms.max(1) as f64 * 2.0);
+            block.structure = Some(BreakFit::Rescued);
+            blocks.push(block);
+        }
+    }
+    for &i in targets {
+        if !assigned[i] {
+            blocks[i].structure = Some(BreakFit::Unexpected);
+        }
+    }
+    score
+}
+
+/// "10,50,95" 形式（パーセント）のCM枠の位置をパース
+pub fn parse_positions(value: &str) -> Option<Vec<f64>> {
+    value
+        .split(',')
+        .map(|v| {
+            let percent: f64 = v.trim().trim_end_matches('%').parse().ok()?;
+            (0.0..=100.0).contains(&percent).then_some(percent / 100.0)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CmCandidate, SegmentKind};
+
+    /// 15秒の標準単位 units 個のブロック
+    fn block(start_ms: i64, units: i64) -> CmBlock {
+        let segments = (0..units)
+            .map(|i| CmCandidate::new(start_ms + i * 15000, start_ms + (i + 1) * 15000, true, SegmentKind::Cm))
+            .collect();
+        CmBlock::new(start_ms, start_ms + units * 15000, segments)
+    }
+
+    #[test]
+    fn test_structure_positions() {
+        // 30分番組で 10% / 50% / 95% にCM枠。50% のブロックは60秒未満で最終フィルタに落ちている
+        let runtime = Range::new(0, 1_800_000);
+        let model = StructureModel {
+            break_count: None,
+            break_positions: parse_positions("10,50,95").unwrap(),
+        };
+        let mut blocks = vec![block(150000, 4), block(1_200_000, 4), block(1_680_000, 6)];
+        let weak = vec![block(880000, 2), block(400000, 2)];
+        let report = apply_structure(&mut blocks, weak, &model, runtime);
+
+        let fits: Vec<(i64, Option<BreakFit>)> = blocks.iter().map(|b| (b.start_ms, b.structure)).collect();
+        assert_eq!(
+            fits,
+            vec![
+                (150000, Some(BreakFit::Expected)),
+                (880000, Some(BreakFit::Rescued)),
+                (1_200_000, Some(BreakFit::Unexpected)),
+                (1_680_000, Some(BreakFit::Expected)),
+            ]
+        );
+        assert_eq!((report.expected_breaks, report.found_breaks, report.rescued_breaks), (3, 4, 1));
+        assert!(report.fit_score > 0.5 && report.fit_score < 0.75);
+    }
+
+    #[test]
+    fn test_structure_count() {
+        let runtime = Range::new(0, 1_800_000);
+        let model = StructureModel {
+            break_count: Some(3),
+            break_positions: Vec::new(),
+        };
+        // 2つしか検出されず、弱いブロックのうち強いもの（45秒）を救済する
+        let mut blocks = vec![block(150000, 4), block(1_680_000, 6)];
+        let weak = vec![block(880000, 2), block(400000, 3), block(2_000_000, 4)];
+        let report = apply_structure(&mut blocks, weak, &model, runtime);
+        let rescued: Vec<i64> = blocks
+            .iter()
+            .filter(|b| b.structure == Some(BreakFit::Rescued))
+            .map(|b| b.start_ms)
+            .collect();
+        assert_eq!(rescued, vec![400000]);
+        assert_eq!(report.fit_score, 1.0);
+
+        // 想定より多い場合は弱いブロックを想定外とする
+        let mut blocks = vec![block(150000, 4), block(600000, 2), block(900000, 5), block(1_680_000, 6)];
+        let report = apply_structure(&mut blocks, Vec::new(), &model, runtime);
+        assert_eq!(blocks[1].structure, Some(BreakFit::Unexpected));
+        assert_eq!(report.unexpected_breaks, 1);
+        assert_eq!(report.fit_score, 0.75);
+
+        assert_eq!(parse_positions("10%, 50"), Some(vec![0.1, 0.5]));
+        assert!(parse_positions("120").is_none());
+    }
+}
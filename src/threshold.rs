@@ -0,0 +1,189 @@
+// 無音判定の閾値（silencedetect の n）の推定
+//
+// 録画ごとに音量が異なるため、固定の -40dB では静かな番組で本編中の間まで無音になり、
+// 音量の大きい局ではCMの境界が無音にならないことがある。
+// - WAV / PCM: 短い区間ごとのピークレベルのヒストグラムからノイズフロアを求め、閾値を決める
+// - ffmpeg のログ等: 検出済みの無音区間の密度から、より適切な n を推奨する
+
+use serde::Serialize;
+
+use crate::SilenceSegment;
+
+const HISTOGRAM_MIN_DB: f64 = -100.0; // これ未満のレベル（デジタル無音を含む）は最下位のビンに入れる
+const FLOOR_PERCENTILE: f64 = 0.005; // ノイズフロアとみなすレベル（下位0.5%）
+const CONTENT_PERCENTILE: f64 = 0.5; // 番組・CMの代表レベル（中央値）
+const FLOOR_MARGIN_DB: f64 = 15.0; // ノイズフロアから閾値までの幅の上限
+const MIN_NOISE_DB: f64 = -90.0;
+const MAX_NOISE_DB: f64 = -20.0;
+const MIN_SILENCES_PER_MINUTE: f64 = 0.5; // これより少なければCMの境界を取りこぼしているとみなす
+const MAX_SILENCES_PER_MINUTE: f64 = 4.0; // これより多ければ本編中の間を拾っているとみなす
+const RECOMMEND_STEP_DB: f64 = 5.0; // 推奨値の調整幅（密度が2倍ずれるごと）
+const MAX_RECOMMEND_STEPS: f64 = 2.0;
+
+/// 閾値の決め方
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ThresholdMethod {
+    /// 音声のレベル分布から推定した閾値で検出した
+    Adaptive,
+    /// ログの無音区間の密度から推奨値を求めた（検出には使用していない）
+    Recommendation,
+}
+
+/// 無音判定の閾値のJSON出力
+#[derive(Debug, Clone, Serialize)]
+pub struct ThresholdReport {
+    pub method: ThresholdMethod,
+    /// Adaptive: 検出に使用した閾値 / Recommendation: 推奨する silencedetect の n
+    pub noise_db: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub noise_floor_db: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_level_db: Option<f64>,
+    /// ログの作成に使われた n（Recommendation のみ）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub assumed_noise_db: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub silences_per_minute: Option<f64>,
+}
+
+/// 振幅（0.0〜1.0）をdBに変換（HISTOGRAM_MIN_DB で下限を切る）
+pub fn amplitude_to_db(amplitude: f64) -> f64 {
+    if amplitude <= 0.0 {
+        return HISTOGRAM_MIN_DB;
+    }
+    (20.0 * amplitude.log10()).max(HISTOGRAM_MIN_DB)
+}
+
+/// 1dB 刻みのレベルのヒストグラム
+struct Histogram {
+    counts: Vec<u64>,
+    total: u64,
+}
+
+impl Histogram {
+    fn new(levels_db: &[f64]) -> Self {
+        let bins = (-HISTOGRAM_MIN_DB) as usize;
+        let mut counts = vec![0u64; bins];
+        for &level in levels_db {
+            let bin = (level - HISTOGRAM_MIN_DB).floor().clamp(0.0, (bins - 1) as f64) as usize;
+            counts[bin] += 1;
+        }
+        Histogram {
+            counts,
+            total: levels_db.len() as u64,
+        }
+    }
+
+    /// 下位 p の位置にあるビンの上端（dB）
+    fn percentile(&self, p: f64) -> f64 {
+        let target = (self.total as f64 * p).ceil().max(1.0) as u64;
+        let mut cumulative = 0;
+        for (bin, &count) in self.counts.iter().enumerate() {
+            cumulative += count;
+            if cumulative >= target {
+                return HISTOGRAM_MIN_DB + bin as f64 + 1.0;
+            }
+        }
+        0.0
+    }
+}
+
+/// レベルの分布からノイズフロアを求め、無音判定の閾値を決める
+/// 閾値はノイズフロアから、代表レベルまでの半分（最大 FLOOR_MARGIN_DB）上
+pub fn estimate_noise_threshold(levels_db: &[f64]) -> ThresholdReport {
+    let histogram = Histogram::new(levels_db);
+    let floor = histogram.percentile(FLOOR_PERCENTILE);
+    let content = histogram.percentile(CONTENT_PERCENTILE);
+    let margin = FLOOR_MARGIN_DB.min((content - floor) / 2.0).max(0.0);
+    ThresholdReport {
+        method: ThresholdMethod::Adaptive,
+        noise_db: (floor + margin).clamp(MIN_NOISE_DB, MAX_NOISE_DB),
+        noise_floor_db: Some(floor),
+        content_level_db: Some(content),
+        assumed_noise_db: None,
+        silences_per_minute: None,
+    }
+}
+
+/// 無音区間の密度から silencedetect の n の推奨値を求める
+/// 密度が多すぎれば n を下げ、少なすぎれば上げる（密度が2倍ずれるごとに RECOMMEND_STEP_DB）
+pub fn recommend_noise_threshold(
+    segments: &[SilenceSegment],
+    duration_ms: Option<i64>,
+    assumed_noise_db: f64,
+) -> ThresholdReport {
+    let duration_ms = duration_ms
+        .or_else(|| segments.iter().map(|s| s.end_ms).max())
+        .unwrap_or(0);
+    let minutes = duration_ms as f64 / 60000.0;
+    let density = if minutes > 0.0 { segments.len() as f64 / minutes } else { 0.0 };
+
+    let steps = if density > MAX_SILENCES_PER_MINUTE {
+        -(density / MAX_SILENCES_PER_MINUTE).log2().ceil()
+    } else if density == 0.0 {
+        MAX_RECOMMEND_STEPS
+    } else if density < MIN_SILENCES_PER_MINUTE {
+        (MIN_SILENCES_PER_MINUTE / density).log2().ceil()
+    } else {
+        0.0
+    };
+    let steps = steps.clamp(-MAX_RECOMMEND_STEPS, MAX_RECOMMEND_STEPS);
+
+    ThresholdReport {
+        method: ThresholdMethod::Recommendation,
+        noise_db: (assumed_noise_db + steps * RECOMMEND_STEP_DB).clamp(MIN_NOISE_DB, MAX_NOISE_DB),
+        noise_floor_db: None,
+        content_level_db: None,
+        assumed_noise_db: Some(assumed_noise_db),
+        silences_per_minute: Some((density * 100.0).round() / 100.0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate_noise_threshold() {
+        // 静かな番組: 本編は -45dB 前後、境界はデジタル無音（1%）
+        let mut levels = vec![-45.5; 990];
+        levels.extend(vec![amplitude_to_db(0.0); 10]);
+        let report = estimate_noise_threshold(&levels);
+        assert_eq!(report.noise_floor_db, Some(-99.0));
+        assert_eq!(report.content_level_db, Some(-45.0));
+        assert_eq!(report.noise_db, -84.0);
+
+        // 音量の大きい局: 境界の無音も -50dB 程度のノイズを含む
+        let mut levels = vec![-12.0; 990];
+        levels.extend(vec![-50.5; 10]);
+        let report = estimate_noise_threshold(&levels);
+        assert_eq!(report.noise_floor_db, Some(-50.0));
+        assert_eq!(report.noise_db, -35.0);
+    }
+
+    #[test]
+    fn test_recommend_noise_threshold() {
+        let silences = |count: i64, interval_ms: i64| -> Vec<SilenceSegment> {
+            (0..count)
+                .map(|i| SilenceSegment {
+                    start_ms: i * interval_ms,
+                    end_ms: i * interval_ms + 500,
+                    duration_ms: 500,
+                })
+                .collect()
+        };
+        // 30分で60個（2個/分）: 変更しない
+        let report = recommend_noise_threshold(&silences(60, 30000), Some(1_800_000), -40.0);
+        assert_eq!(report.noise_db, -40.0);
+        assert_eq!(report.silences_per_minute, Some(2.0));
+        // 30分で360個（12個/分）: 2段階下げる
+        let report = recommend_noise_threshold(&silences(360, 5000), Some(1_800_000), -40.0);
+        assert_eq!(report.noise_db, -50.0);
+        // 30分で6個（0.2個/分）: 上げる
+        let report = recommend_noise_threshold(&silences(6, 300000), Some(1_800_000), -40.0);
+        assert_eq!(report.noise_db, -30.0);
+        // 無音区間なし
+        assert_eq!(recommend_noise_threshold(&[], None, -40.0).noise_db, -30.0);
+    }
+}
@@ -0,0 +1,202 @@
+// RFC3339 形式の壁時計時刻
+
+/// UTCからのオフセット付きの時刻
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timestamp {
+    /// UNIXエポックからのミリ秒
+    pub unix_ms: i64,
+    /// UTCからのオフセット（秒）。JSTなら +32400
+    pub offset_sec: i64,
+}
+
+impl Timestamp {
+    /// RFC3339 形式（例: 2024-04-01T21:00:00+09:00, 2024-04-01T12:00:00.5Z）をパース
+    pub fn parse_rfc3339(value: &str) -> Option<Timestamp> {
+        let value = value.trim();
+        let (date, rest) = value.split_at_checked(10)?;
+        let rest = rest.strip_prefix(['T', 't', ' '])?;
+
+        let mut date_parts = date.split('-');
+        let year: i64 = date_parts.next()?.parse().ok()?;
+        let month: i64 = date_parts.next()?.parse().ok()?;
+        let day: i64 = date_parts.next()?.parse().ok()?;
+        if !(1..=12).contains(&month) || !(1..=days_in_month(year, month)).contains(&day) {
+            return None;
+        }
+
+        // 時刻部とオフセット部を分離
+        let (time, offset_sec) = if let Some(time) = rest.strip_suffix(['Z', 'z']) {
+            (time, 0)
+        } else {
+            let sign_pos = rest.rfind(['+', '-'])?;
+            let (time, offset) = rest.split_at(sign_pos);
+            let sign = if offset.starts_with('-') { -1 } else { 1 };
+            let (hours, minutes) = offset[1..].split_once(':')?;
+            let hours: i64 = hours.parse().ok()?;
+            let minutes: i64 = minutes.parse().ok()?;
+            if !(0..=23).contains(&hours) || !(0..=59).contains(&minutes) {
+                return None;
+            }
+            (time, sign * (hours * 3600 + minutes * 60))
+        };
+
+        let mut time_parts = time.split(':');
+        let hour: i64 = time_parts.next()?.parse().ok()?;
+        let minute: i64 = time_parts.next()?.parse().ok()?;
+        let second: f64 = time_parts.next()?.parse().ok()?;
+        if time_parts.next().is_some() || hour > 23 || minute > 59 || !(0.0..61.0).contains(&second) {
+            return None;
+        }
+
+        let days = days_from_civil(year, month, day);
+        let local_ms = (days * 86400 + hour * 3600 + minute * 60) * 1000 + (second * 1000.0).round() as i64;
+        Some(Timestamp {
+            unix_ms: local_ms - offset_sec * 1000,
+            offset_sec,
+        })
+    }
+
+    /// ミリ秒後の時刻（オフセットは維持）
+    pub fn add_ms(&self, ms: i64) -> Timestamp {
+        Timestamp {
+            unix_ms: self.unix_ms + ms,
+            offset_sec: self.offset_sec,
+        }
+    }
+
+    /// 同じ時刻を別のオフセットで表す
+    pub fn with_offset(&self, offset_sec: i64) -> Timestamp {
+        Timestamp {
+            unix_ms: self.unix_ms,
+            offset_sec,
+        }
+    }
+
+    /// RFC3339 形式（ミリ秒精度）で出力。例: 2024-04-01T21:02:00.500+09:00
+    pub fn to_rfc3339(self) -> String {
+        let local_ms = self.unix_ms + self.offset_sec * 1000;
+        let days = local_ms.div_euclid(86_400_000);
+        let ms_of_day = local_ms.rem_euclid(86_400_000);
+        let (year, month, day) = civil_from_days(days);
+
+        let offset = if self.offset_sec == 0 {
+            "Z".to_string()
+        } else {
+            let sign = if self.offset_sec < 0 { '-' } else { '+' };
+            let abs = self.offset_sec.abs();
+            format!("{}{:02}:{:02}", sign, abs / 3600, abs % 3600 / 60)
+        };
+
+        format!(
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}{}",
+            year,
+            month,
+            day,
+            ms_of_day / 3_600_000,
+            ms_of_day / 60_000 % 60,
+            ms_of_day / 1000 % 60,
+            ms_of_day % 1000,
+            offset
+        )
+    }
+
+    /// 現地時刻での0時からの経過秒
+    pub fn local_seconds_of_day(&self) -> i64 {
+        (self.unix_ms.div_euclid(1000) + self.offset_sec).rem_euclid(86400)
+    }
+}
+
+/// グレゴリオ暦の月の日数
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// グレゴリオ暦の日付から1970-01-01からの日数を計算
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_index = (month + 9) % 12; // 3月始まり
+    let day_of_year = (153 * month_index + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+/// 1970-01-01からの日数からグレゴリオ暦の日付を計算（days_from_civil の逆変換）
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rfc3339() {
+        let ts = Timestamp::parse_rfc3339("1970-01-01T00:00:00Z").unwrap();
+        assert_eq!(ts, Timestamp { unix_ms: 0, offset_sec: 0 });
+
+        // JST 21:00 = UTC 12:00
+        let ts = Timestamp::parse_rfc3339("2024-04-01T21:00:00+09:00").unwrap();
+        assert_eq!(ts.unix_ms, 1711972800000);
+        assert_eq!(ts.offset_sec, 9 * 3600);
+        assert_eq!(ts.local_seconds_of_day(), 21 * 3600);
+
+        let ts = Timestamp::parse_rfc3339("2024-02-29T23:59:59.250-05:00").unwrap();
+        assert_eq!(ts.unix_ms, 1709269199250);
+
+        assert!(Timestamp::parse_rfc3339("2024-04-01").is_none());
+        assert!(Timestamp::parse_rfc3339("2024-13-01T00:00:00Z").is_none());
+        assert!(Timestamp::parse_rfc3339("2024-04-01T21:00:00").is_none());
+    }
+
+    #[test]
+    fn test_parse_rfc3339_rejects_invalid_dates() {
+        // 月の日数とうるう年
+        assert!(Timestamp::parse_rfc3339("2024-04-31T00:00:00Z").is_none());
+        assert!(Timestamp::parse_rfc3339("2023-02-29T00:00:00Z").is_none());
+        assert!(Timestamp::parse_rfc3339("1900-02-29T00:00:00Z").is_none());
+        assert!(Timestamp::parse_rfc3339("2000-02-29T00:00:00Z").is_some());
+        assert!(Timestamp::parse_rfc3339("2024-12-31T00:00:00Z").is_some());
+
+        // オフセットは時 23、分 59 まで
+        assert!(Timestamp::parse_rfc3339("2024-04-01T21:00:00+24:00").is_none());
+        assert!(Timestamp::parse_rfc3339("2024-04-01T21:00:00+09:60").is_none());
+        assert!(Timestamp::parse_rfc3339("2024-04-01T21:00:00-23:59").is_some());
+    }
+
+    #[test]
+    fn test_local_seconds_of_day_wraps() {
+        // JST 08:59:30 → 翌日にまたがらずに計算される
+        let ts = Timestamp::parse_rfc3339("2024-04-01T08:59:30+09:00").unwrap();
+        assert_eq!(ts.local_seconds_of_day(), 8 * 3600 + 59 * 60 + 30);
+        assert_eq!(ts.add_ms(60_000).local_seconds_of_day(), 9 * 3600 + 30);
+    }
+
+    #[test]
+    fn test_to_rfc3339() {
+        let ts = Timestamp::parse_rfc3339("2024-04-01T21:00:00+09:00").unwrap();
+        assert_eq!(ts.to_rfc3339(), "2024-04-01T21:00:00.000+09:00");
+        assert_eq!(ts.add_ms(10_800_500).to_rfc3339(), "2024-04-02T00:00:00.500+09:00");
+        assert_eq!(ts.with_offset(0).to_rfc3339(), "2024-04-01T12:00:00.000Z");
+
+        // うるう日をまたぐ往復
+        let ts = Timestamp::parse_rfc3339("2024-02-29T23:59:59.250-05:00").unwrap();
+        assert_eq!(ts.to_rfc3339(), "2024-02-29T23:59:59.250-05:00");
+        assert_eq!(Timestamp::parse_rfc3339(&ts.to_rfc3339()), Some(ts));
+    }
+}
@@ -0,0 +1,250 @@
+// 正解付きの履歴からの放送局ごとのパラメータの学習（cm-detector train）
+//
+// 無音区間のログと手作業で修正したCM区間の組から、単位の判定の許容誤差・短時間単位・最終フィルタの条件を
+// 格子探索し、全ファイルの時間の重なりによる F1 が最大になる組み合わせをプロファイルとして書き出す。
+// 探索範囲と順序は固定で、同点の場合は先に現れる（デフォルトに近い）値を選ぶため、結果は常に同じになる。
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::audio::AudioOptions;
+use crate::eval::{self, OverlapCounts, Interval};
+use crate::input::{self, InputFormat};
+use crate::parser::ParsedLog;
+use crate::profile::{self, Profile};
+use crate::{CliArgs, DetectorConfig, detect_from_parsed};
+
+// 探索範囲（先頭はデフォルト値）
+const TOLERANCES_MS: [i64; 7] = [500, 300, 400, 600, 700, 800, 1000];
+const SHORT_UNIT_SETS: [&[f64]; 4] = [&[5.0, 10.0], &[5.0], &[10.0], &[]];
+const MIN_STANDARD_UNITS: [usize; 4] = [2, 1, 3, 4];
+const MIN_BLOCK_DURATIONS_SEC: [f64; 5] = [60.0, 30.0, 45.0, 75.0, 90.0];
+const F1_EPSILON: f64 = 1e-9; // これ以下の F1 の差は同点とみなす
+
+/// train サブコマンドの引数
+#[derive(Debug, Default)]
+struct TrainArgs {
+    /// (ログ, 正解) の組
+    pairs: Vec<(String, String)>,
+    input_format: InputFormat,
+    /// 学習しない項目（ブリッジ区間・間隔の計測方法など）を引き継ぐプロファイル
+    base_profile: Option<String>,
+    channel_id: Option<String>,
+    /// プロファイルの出力先（省略時は標準出力）
+    output: Option<String>,
+}
+
+fn parse_train_args(args: impl IntoIterator<Item = String>) -> Result<TrainArgs, String> {
+    let mut train = TrainArgs::default();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--pair" => {
+                let log = args.next().ok_or("--pair requires LOG and TRUTH")?;
+                let truth = args.next().ok_or("--pair requires LOG and TRUTH")?;
+                train.pairs.push((log, truth));
+            }
+            "--input-format" => {
+                let value = args.next().ok_or("--input-format requires a value")?;
+                train.input_format = InputFormat::parse(&value).ok_or_else(|| {
+                    format!("Unknown input format: {} (expected ffmpeg, json, csv, wav or pcm)", value)
+                })?;
+            }
+            "--profile" => {
+                let value = args.next().ok_or("--profile requires a value")?;
+                train.base_profile = Some(value);
+            }
+            "--channel-id" => {
+                let value = args.next().ok_or("--channel-id requires a value")?;
+                train.channel_id = Some(value);
+            }
+            "--output" => {
+                let value = args.next().ok_or("--output requires a value")?;
+                train.output = Some(value);
+            }
+            _ => return Err(format!("Unknown argument: {}", arg)),
+        }
+    }
+    if train.pairs.is_empty() {
+        return Err("train requires at least one --pair LOG TRUTH".to_string());
+    }
+    Ok(train)
+}
+
+/// 学習に使う1ファイル分のデータ
+struct Sample {
+    name: String,
+    parsed: ParsedLog,
+    truth: Vec<Interval>,
+}
+
+fn load_sample(log: &str, truth: &str, format: InputFormat) -> Result<Sample, String> {
+    let file = fs::File::open(log).map_err(|e| format!("Failed to open {}: {}", log, e))?;
+    Ok(Sample {
+        name: log.to_string(),
+        parsed: input::read_input(format, &mut io::BufReader::new(file), &AudioOptions::default())?,
+        truth: eval::read_intervals(truth)?,
+    })
+}
+
+/// main と同じ検出を経過を表示せずに行い、CMブロックと正解の重なりを求める
+fn score(sample: &Sample, config: &DetectorConfig) -> Result<OverlapCounts, String> {
+    let cli = CliArgs {
+        config: config.clone(),
+        ..CliArgs::default()
+    };
+    let detection = detect_from_parsed(sample.parsed.clone(), &cli, false)?;
+    let pred: Vec<Interval> = detection.blocks.iter().map(Interval::from).collect();
+    Ok(OverlapCounts::new(&sample.truth, &eval::normalize(pred)))
+}
+
+/// 全ファイルの重なりの合計
+fn evaluate(samples: &[Sample], config: &DetectorConfig) -> Result<OverlapCounts, String> {
+    let mut total = OverlapCounts::default();
+    for sample in samples {
+        total.add(&score(sample, config)?);
+    }
+    Ok(total)
+}
+
+/// 格子探索で F1 が最大になるパラメータを求める
+fn fit(samples: &[Sample], base: &DetectorConfig) -> Result<(DetectorConfig, OverlapCounts), String> {
+    let mut best: Option<(DetectorConfig, OverlapCounts)> = None;
+    for tolerance_ms in TOLERANCES_MS {
+        for short_units in SHORT_UNIT_SETS {
+            for min_standard_units in MIN_STANDARD_UNITS {
+                for min_block_duration_sec in MIN_BLOCK_DURATIONS_SEC {
+                    let config = DetectorConfig {
+                        tolerance_ms,
+                        short_units: short_units.to_vec(),
+                        min_standard_units,
+                        min_block_duration_sec,
+                        ..base.clone()
+                    };
+                    let total = evaluate(samples, &config)?;
+                    if best.as_ref().is_none_or(|(_, b)| total.f1() > b.f1() + F1_EPSILON) {
+                        best = Some((config, total));
+                    }
+                }
+            }
+        }
+    }
+    Ok(best.expect("search space is not empty"))
+}
+
+/// train サブコマンド
+pub fn run(args: impl IntoIterator<Item = String>) -> Result<(), String> {
+    let args = parse_train_args(args)?;
+    let mut profile = match &args.base_profile {
+        Some(path) => profile::read_profile(Path::new(path))?,
+        None => Profile::default(),
+    };
+    let mut base = DetectorConfig::default();
+    profile.apply(&mut base)?;
+
+    let samples = args
+        .pairs
+        .iter()
+        .map(|(log, truth)| load_sample(log, truth, args.input_format))
+        .collect::<Result<Vec<_>, String>>()?;
+    eprintln!("Training on {} files...", samples.len());
+
+    let (config, total) = fit(&samples, &base)?;
+    for sample in &samples {
+        let before = score(sample, &base)?;
+        let after = score(sample, &config)?;
+        eprintln!("  {}: F1 {:.3} -> {:.3}", sample.name, before.f1(), after.f1());
+    }
+    eprintln!(
+        "F1 {:.3} -> {:.3} (precision {:.3}, recall {:.3})",
+        evaluate(&samples, &base)?.f1(),
+        total.f1(),
+        total.precision(),
+        total.recall()
+    );
+    eprintln!(
+        "Tolerance {}ms, short units {:?}, min standard units {}, min block duration {}s",
+        config.tolerance_ms,
+        config.short_units,
+        config.min_standard_units,
+        config.min_block_duration_sec
+    );
+
+    if args.channel_id.is_some() {
+        profile.channel_id = args.channel_id;
+    }
+    profile.tolerance_ms = Some(config.tolerance_ms);
+    profile.short_units = Some(config.short_units);
+    profile.min_standard_units = Some(config.min_standard_units);
+    profile.min_block_duration_sec = Some(config.min_block_duration_sec);
+
+    let json = serde_json::to_string_pretty(&profile).expect("Failed to serialize JSON");
+    match &args.output {
+        Some(path) => {
+            fs::write(path, json + "\n").map_err(|e| format!("Failed to write {}: {}", path, e))?;
+            eprintln!("Wrote profile to {}", path);
+        }
+        None => println!("{}", json),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SilenceSegment;
+
+    /// times_ms を中心とする 0.5秒の無音区間
+    fn silences(times_ms: &[i64]) -> Vec<SilenceSegment> {
+        times_ms
+            .iter()
+            .map(|&t| SilenceSegment {
+                start_ms: t - 250,
+                end_ms: t + 250,
+                duration_ms: 500,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_fit() {
+        // 15秒CMが 15.7秒間隔で記録される局（デフォルトの許容誤差 500ms ではチェーンが切れる）
+        let cm = |start_ms: i64| (0..5).map(move |i| start_ms + i * 15700);
+        let times: Vec<i64> = cm(300000).chain(cm(900000)).collect();
+        let sample = Sample {
+            name: "a.log".to_string(),
+            parsed: ParsedLog {
+                silence_segments: silences(&times),
+                ..ParsedLog::default()
+            },
+            truth: vec![
+                Interval { start_ms: 300000, end_ms: 362800 },
+                Interval { start_ms: 900000, end_ms: 962800 },
+            ],
+        };
+        let base = DetectorConfig::default();
+        assert_eq!(evaluate(std::slice::from_ref(&sample), &base).unwrap().f1(), 0.0);
+
+        let (config, total) = fit(std::slice::from_ref(&sample), &base).unwrap();
+        assert_eq!(total.f1(), 1.0);
+        // 15.7秒間隔をつなげる探索範囲内で最小の許容誤差
+        assert_eq!(config.tolerance_ms, 600);
+        // 同点の項目はデフォルトのまま
+        assert_eq!(config.short_units, vec![5.0, 10.0]);
+        assert_eq!(config.min_standard_units, 2);
+        assert_eq!(config.min_block_duration_sec, 60.0);
+    }
+
+    #[test]
+    fn test_parse_train_args() {
+        let args = ["--pair", "a.log", "a.json", "--pair", "b.log", "b.json", "--output", "BS11.json"]
+            .iter()
+            .map(|s| s.to_string());
+        let train = parse_train_args(args).unwrap();
+        assert_eq!(train.pairs.len(), 2);
+        assert_eq!(train.output.as_deref(), Some("BS11.json"));
+        assert!(parse_train_args(Vec::new()).is_err());
+        assert!(parse_train_args(["--pair".to_string(), "a.log".to_string()]).is_err());
+    }
+}
@@ -0,0 +1,127 @@
+// 無音区間の整合性チェック（ログの異常の検出と修復）
+
+use serde::Serialize;
+
+use crate::SilenceSegment;
+
+/// ログの異常の種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnomalyKind {
+    /// silence_duration が silence_end - silence_start と一致しない
+    DurationMismatch,
+    /// 開始時刻が前の無音区間より前（ログの連結・PTSのリセット）
+    NonMonotonic,
+    /// 前の無音区間と重なる
+    Overlap,
+    /// 前の無音区間と同じ（同じ行の重複）
+    Duplicate,
+}
+
+/// 検出されたログの異常
+#[derive(Debug, Clone, Serialize)]
+pub struct Anomaly {
+    pub kind: AnomalyKind,
+    pub time_ms: i64,
+    pub detail: String,
+}
+
+/// 異常があった場合の扱い
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ValidationMode {
+    /// 報告のみ（そのまま検出に使う）
+    #[default]
+    Warn,
+    /// 時刻順に並べ替え、重複を除き、重なる区間を結合する
+    Repair,
+    /// 異常があればエラーにする
+    Reject,
+}
+
+impl ValidationMode {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "warn" => Some(ValidationMode::Warn),
+            "repair" => Some(ValidationMode::Repair),
+            "reject" => Some(ValidationMode::Reject),
+            _ => None,
+        }
+    }
+}
+
+/// 隣接する無音区間の順序・重なり・重複をチェック
+pub fn check_silences(segments: &[SilenceSegment]) -> Vec<Anomaly> {
+    let mut anomalies = Vec::new();
+    for pair in segments.windows(2) {
+        let (prev, curr) = (&pair[0], &pair[1]);
+        let kind = if curr.start_ms == prev.start_ms && curr.end_ms == prev.end_ms {
+            AnomalyKind::Duplicate
+        } else if curr.start_ms < prev.start_ms {
+            AnomalyKind::NonMonotonic
+        } else if curr.start_ms < prev.end_ms {
+            AnomalyKind::Overlap
+        } else {
+            continue;
+        };
+        anomalies.push(Anomaly {
+            kind,
+            time_ms: curr.start_ms,
+            detail: format!(
+                "{}-{}ms follows {}-{}ms",
+                curr.start_ms, curr.end_ms, prev.start_ms, prev.end_ms
+            ),
+        });
+    }
+    anomalies
+}
+
+/// 時刻順に並べ替え、重複・重なる区間を結合する
+pub fn repair_silences(segments: &[SilenceSegment]) -> Vec<SilenceSegment> {
+    let mut sorted = segments.to_vec();
+    sorted.sort_by_key(|s| (s.start_ms, s.end_ms));
+    let mut result: Vec<SilenceSegment> = Vec::new();
+    for s in sorted {
+        match result.last_mut() {
+            Some(last) if s.start_ms <= last.end_ms => {
+                last.end_ms = last.end_ms.max(s.end_ms);
+                last.duration_ms = last.end_ms - last.start_ms;
+            }
+            _ => result.push(s),
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn silence(start_ms: i64, end_ms: i64) -> SilenceSegment {
+        SilenceSegment {
+            start_ms,
+            end_ms,
+            duration_ms: end_ms - start_ms,
+        }
+    }
+
+    #[test]
+    fn test_check_and_repair_silences() {
+        let segments = vec![
+            silence(10000, 10500),
+            silence(10000, 10500),
+            silence(10300, 11000),
+            silence(5000, 5500),
+            silence(20000, 20500),
+        ];
+        let kinds: Vec<AnomalyKind> = check_silences(&segments).iter().map(|a| a.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![AnomalyKind::Duplicate, AnomalyKind::Overlap, AnomalyKind::NonMonotonic]
+        );
+
+        let repaired = repair_silences(&segments);
+        let bounds: Vec<(i64, i64)> = repaired.iter().map(|s| (s.start_ms, s.end_ms)).collect();
+        assert_eq!(bounds, vec![(5000, 5500), (10000, 11000), (20000, 20500)]);
+        assert!(check_silences(&repaired).is_empty());
+    }
+}